@@ -13,9 +13,12 @@ pub struct BoostCommand {
     #[clap(short, long)]
     factor: f32,
 
-    /// Output file
+    /// Output file. Use - to write to stdout
     #[clap(short, long)]
     output: String,
+
+    #[clap(flatten)]
+    encoder_options: encoders::core::EncoderOptions,
 }
 
 #[derive(Debug, Error)]
@@ -30,15 +33,18 @@ pub enum BoostError {
     DecodeError(errors::Error),
     #[error("{0}")]
     EncodeError(encoders::errors::Error),
+    #[error("No supported audio track found")]
+    NoTrackError,
+    #[error("Unknown channel layout")]
+    UnknownChannelLayoutError,
+    #[error("Invalid sample rate")]
+    InvalidSampleRateError,
 }
 
 impl BoostCommand {
     pub fn execute(&self, input: &str) -> Result<(), BoostError> {
-        let input_path = utils::to_absolute_path(&input);
-        let output_path = utils::to_absolute_path(&self.output);
-
         let mut format =
-            audio_utils::get_audio_format(&input_path).map_err(|e| BoostError::ProbeError(e))?;
+            audio_utils::get_audio_format(input).map_err(|e| BoostError::ProbeError(e))?;
 
         // Default track or find the first non-null track
         let track = format
@@ -49,19 +55,31 @@ impl BoostCommand {
                     .iter()
                     .find(|t| t.codec_params.codec != codecs::CODEC_TYPE_NULL)
             })
-            .unwrap();
+            .ok_or(BoostError::NoTrackError)?;
 
         let track_id = track.id;
 
-        let channels = track.codec_params.channels.unwrap().count();
-        let sample_rate = track.codec_params.sample_rate.unwrap();
+        let channels = track
+            .codec_params
+            .channels
+            .ok_or(BoostError::UnknownChannelLayoutError)?
+            .count();
+        let sample_rate = track
+            .codec_params
+            .sample_rate
+            .ok_or(BoostError::InvalidSampleRateError)?;
         let mut decoder = default::get_codecs()
             .make(&track.codec_params, &Default::default())
             .map_err(|e| BoostError::SymphoniaError(e))?;
 
         let mut sample_buffer: Option<audio::SampleBuffer<f32>> = None;
-        let mut writer = encoders::core::get_encoder(&output_path, channels as u16, sample_rate)
-            .map_err(|e| BoostError::EncodeError(e))?;
+        let mut writer = encoders::core::get_encoder(
+            &self.output,
+            &self.encoder_options,
+            channels as u16,
+            sample_rate,
+        )
+        .map_err(|e| BoostError::EncodeError(e))?;
 
         loop {
             let packet = match format.next_packet() {
@@ -117,7 +135,12 @@ impl BoostCommand {
             }
         }
 
-        println!("Audio saved to {}", output_path.display());
+        writer.finish().map_err(|e| BoostError::EncodeError(e))?;
+
+        if !audio_utils::is_standard_stream(&self.output) {
+            let output_path = utils::to_absolute_path(&self.output);
+            println!("Audio saved to {}", output_path.display());
+        }
 
         return Ok(());
     }
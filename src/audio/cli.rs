@@ -6,7 +6,12 @@ use thiserror::Error;
 
 use super::boost::{BoostCommand, BoostError};
 use super::convert::{AudioConvertCommand, AudioConvertError};
+use super::convolve::{ConvolveCommand, ConvolveError};
+use super::fingerprint::{FingerprintCommand, FingerprintError};
+use super::formats::AudioFormatsCommand;
+use super::pitch::{PitchCommand, PitchError};
 use super::speed::{AudioSpeedCommand, AudioSpeedError};
+use super::tempo::{TempoCommand, TempoError};
 
 #[derive(Subcommand)]
 pub enum AudioCommand {
@@ -21,6 +26,27 @@ pub enum AudioCommand {
     /// Boost the volume of an audio file
     #[clap(name = "boost")]
     Boost(BoostCommand),
+
+    /// Apply an impulse response to an audio file, such as a room reverb
+    #[clap(name = "convolve")]
+    Convolve(ConvolveCommand),
+
+    /// Compute the fingerprint of an audio file, or find duplicates in a
+    /// directory
+    #[clap(name = "fingerprint")]
+    Fingerprint(FingerprintCommand),
+
+    /// Shift the pitch of an audio file without changing its duration
+    #[clap(name = "pitch")]
+    Pitch(PitchCommand),
+
+    /// Estimate the tempo of an audio file
+    #[clap(name = "tempo")]
+    Tempo(TempoCommand),
+
+    /// List the audio formats that can be decoded and encoded
+    #[clap(name = "formats")]
+    Formats(AudioFormatsCommand),
 }
 
 impl fmt::Display for AudioCommand {
@@ -29,6 +55,11 @@ impl fmt::Display for AudioCommand {
             AudioCommand::Convert(_) => write!(f, "convert"),
             AudioCommand::Speed(_) => write!(f, "speed"),
             AudioCommand::Boost(_) => write!(f, "boost"),
+            AudioCommand::Convolve(_) => write!(f, "convolve"),
+            AudioCommand::Fingerprint(_) => write!(f, "fingerprint"),
+            AudioCommand::Pitch(_) => write!(f, "pitch"),
+            AudioCommand::Tempo(_) => write!(f, "tempo"),
+            AudioCommand::Formats(_) => write!(f, "formats"),
         }
     }
 }
@@ -41,6 +72,14 @@ pub enum AudioError {
     SpeedError(AudioSpeedError),
     #[error("{0}")]
     BoostError(BoostError),
+    #[error("{0}")]
+    ConvolveError(ConvolveError),
+    #[error("{0}")]
+    FingerprintError(FingerprintError),
+    #[error("{0}")]
+    PitchError(PitchError),
+    #[error("{0}")]
+    TempoError(TempoError),
     #[error("No input file provided")]
     NoInputError,
 }
@@ -49,6 +88,11 @@ impl AudioCommand {
     pub fn execute(&self, input: Option<&str>) -> Result<(), AudioError> {
         info!("Detected operation: {}", self);
 
+        if let AudioCommand::Formats(command) = self {
+            command.execute();
+            return Ok(());
+        }
+
         match input {
             Some(input) => match self {
                 AudioCommand::Convert(command) => command
@@ -60,6 +104,19 @@ impl AudioCommand {
                 AudioCommand::Boost(command) => command
                     .execute(input)
                     .map_err(|e| AudioError::BoostError(e)),
+                AudioCommand::Convolve(command) => command
+                    .execute(input)
+                    .map_err(|e| AudioError::ConvolveError(e)),
+                AudioCommand::Fingerprint(command) => command
+                    .execute(input)
+                    .map_err(|e| AudioError::FingerprintError(e)),
+                AudioCommand::Pitch(command) => command
+                    .execute(input)
+                    .map_err(|e| AudioError::PitchError(e)),
+                AudioCommand::Tempo(command) => command
+                    .execute(input)
+                    .map_err(|e| AudioError::TempoError(e)),
+                AudioCommand::Formats(_) => Ok(()),
             },
             None => Err(AudioError::NoInputError),
         }
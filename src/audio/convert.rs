@@ -1,5 +1,5 @@
 use clap::Args;
-use log::{warn};
+use log::warn;
 use symphonia::core::{audio, codecs, errors};
 use symphonia::default;
 use thiserror::Error;
@@ -10,9 +10,12 @@ use crate::internal::utils;
 
 #[derive(Args)]
 pub struct AudioConvertCommand {
-    /// Output file
+    /// Output file. Use - to write to stdout
     #[clap(short, long)]
     output: String,
+
+    #[clap(flatten)]
+    encoder_options: encoders::core::EncoderOptions,
 }
 
 #[derive(Debug, Error)]
@@ -27,15 +30,18 @@ pub enum AudioConvertError {
     DecodeError(errors::Error),
     #[error("{0}")]
     EncodeError(encoders::errors::Error),
+    #[error("No supported audio track found")]
+    NoTrackError,
+    #[error("Unknown channel layout")]
+    UnknownChannelLayoutError,
+    #[error("Invalid sample rate")]
+    InvalidSampleRateError,
 }
 
 impl AudioConvertCommand {
     pub fn execute(&self, input: &str) -> Result<(), AudioConvertError> {
-        let input_path = utils::to_absolute_path(&input);
-        let output_path = utils::to_absolute_path(&self.output);
-
-        let mut format = audio_utils::get_audio_format(&input_path)
-            .map_err(|e| AudioConvertError::ProbeError(e))?;
+        let mut format =
+            audio_utils::get_audio_format(input).map_err(|e| AudioConvertError::ProbeError(e))?;
 
         // Default track or find the first non-null track
         let track = format
@@ -46,19 +52,31 @@ impl AudioConvertCommand {
                     .iter()
                     .find(|t| t.codec_params.codec != codecs::CODEC_TYPE_NULL)
             })
-            .unwrap();
+            .ok_or(AudioConvertError::NoTrackError)?;
 
         let track_id = track.id;
 
-        let channels = track.codec_params.channels.unwrap().count();
-        let sample_rate = track.codec_params.sample_rate.unwrap();
+        let channels = track
+            .codec_params
+            .channels
+            .ok_or(AudioConvertError::UnknownChannelLayoutError)?
+            .count();
+        let sample_rate = track
+            .codec_params
+            .sample_rate
+            .ok_or(AudioConvertError::InvalidSampleRateError)?;
         let mut decoder = default::get_codecs()
             .make(&track.codec_params, &Default::default())
             .map_err(|e| AudioConvertError::SymphoniaError(e))?;
 
         let mut sample_buffer: Option<audio::SampleBuffer<f32>> = None;
-        let mut writer = encoders::core::get_encoder(&output_path, channels as u16, sample_rate)
-            .map_err(|e| AudioConvertError::EncodeError(e))?;
+        let mut writer = encoders::core::get_encoder(
+            &self.output,
+            &self.encoder_options,
+            channels as u16,
+            sample_rate,
+        )
+        .map_err(|e| AudioConvertError::EncodeError(e))?;
 
         loop {
             let packet = match format.next_packet() {
@@ -108,7 +126,14 @@ impl AudioConvertCommand {
             }
         }
 
-        println!("Audio saved to {}", output_path.display());
+        writer
+            .finish()
+            .map_err(|e| AudioConvertError::EncodeError(e))?;
+
+        if !audio_utils::is_standard_stream(&self.output) {
+            let output_path = utils::to_absolute_path(&self.output);
+            println!("Audio saved to {}", output_path.display());
+        }
 
         Ok(())
     }
@@ -0,0 +1,119 @@
+use clap::Args;
+use thiserror::Error;
+
+use super::dsp::convolve::{self, Convolver};
+use super::dsp::resample::Resampler;
+use super::{encoders, utils as audio_utils};
+use crate::internal::utils;
+
+#[derive(Args)]
+pub struct ConvolveCommand {
+    /// Impulse response to convolve the input with, such as a room recording
+    #[clap(short, long)]
+    ir: String,
+
+    /// Proportion of the convolved signal in the output (0.0 = dry only)
+    /// (1.0 = wet only)
+    #[clap(short, long, default_value = "0.5")]
+    mix: f32,
+
+    /// Scale the impulse response to unit energy before applying it
+    #[clap(short, long)]
+    normalize: bool,
+
+    /// Output file. Use - to write to stdout
+    #[clap(short, long)]
+    output: String,
+
+    #[clap(flatten)]
+    encoder_options: encoders::core::EncoderOptions,
+}
+
+#[derive(Debug, Error)]
+pub enum ConvolveError {
+    #[error("{0}")]
+    DecodeError(audio_utils::DecodeAudioError),
+    #[error("{0}")]
+    ImpulseResponseError(audio_utils::DecodeAudioError),
+    #[error("{0}")]
+    EncodeError(encoders::errors::Error),
+    #[error("Impulse response is empty")]
+    EmptyImpulseResponseError,
+    #[error("Mix must be between 0 and 1")]
+    InvalidMixError,
+}
+
+impl ConvolveCommand {
+    pub fn execute(&self, input: &str) -> Result<(), ConvolveError> {
+        if !(0f32..=1f32).contains(&self.mix) {
+            return Err(ConvolveError::InvalidMixError);
+        }
+
+        let mut stream =
+            audio_utils::AudioStream::open(input).map_err(|e| ConvolveError::DecodeError(e))?;
+
+        let mut impulse_response = self.load_impulse_response(stream.sample_rate)?;
+
+        if self.normalize {
+            convolve::normalize(&mut impulse_response);
+        }
+
+        let mut writer = encoders::core::get_encoder(
+            &self.output,
+            &self.encoder_options,
+            stream.channels as u16,
+            stream.sample_rate,
+        )
+        .map_err(|e| ConvolveError::EncodeError(e))?;
+
+        let mut convolver = Convolver::new(&impulse_response, stream.channels, self.mix);
+
+        while let Some(samples) = stream
+            .next_samples()
+            .map_err(|e| ConvolveError::DecodeError(e))?
+        {
+            writer
+                .encode(&convolver.process(samples))
+                .map_err(|e| ConvolveError::EncodeError(e))?;
+        }
+
+        writer
+            .encode(&convolver.flush())
+            .map_err(|e| ConvolveError::EncodeError(e))?;
+
+        writer.finish().map_err(|e| ConvolveError::EncodeError(e))?;
+
+        if !audio_utils::is_standard_stream(&self.output) {
+            let output_path = utils::to_absolute_path(&self.output);
+            println!("Audio saved to {}", output_path.display());
+        }
+
+        return Ok(());
+    }
+
+    /// Decodes the impulse response into planar samples at the sample rate
+    /// of the input
+    fn load_impulse_response(&self, sample_rate: u32) -> Result<Vec<Vec<f32>>, ConvolveError> {
+        let mut stream = audio_utils::AudioStream::open(&self.ir)
+            .map_err(|e| ConvolveError::ImpulseResponseError(e))?;
+
+        let channels = stream.channels;
+        let mut resampler = Resampler::new(stream.sample_rate, sample_rate, channels);
+        let mut samples = Vec::new();
+
+        while let Some(block) = stream
+            .next_samples()
+            .map_err(|e| ConvolveError::ImpulseResponseError(e))?
+        {
+            samples.extend(resampler.process(block));
+        }
+
+        samples.extend(resampler.flush());
+
+        if samples.is_empty() {
+            return Err(ConvolveError::EmptyImpulseResponseError);
+        }
+
+        return Ok(audio_utils::interleaved_to_planar(&samples, channels));
+    }
+}
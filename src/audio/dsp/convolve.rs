@@ -0,0 +1,189 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use rustfft::num_complex::Complex;
+use rustfft::{Fft, FftPlanner};
+
+/// Length of each impulse response partition, which is also the number of
+/// frames processed at once
+const BLOCK_SIZE: usize = 1024;
+
+/// Streaming convolver working on interleaved samples. The impulse response
+/// is split in partitions of equal size that are applied with overlap-save,
+/// so long responses do not require a huge FFT.
+pub struct Convolver {
+    channels: Vec<ChannelConvolver>,
+    partitions: Vec<Vec<Vec<Complex<f32>>>>,
+    forward: Arc<dyn Fft<f32>>,
+    inverse: Arc<dyn Fft<f32>>,
+    mix: f32,
+    pending: Vec<f32>,
+    tail_frames: usize,
+    input_frames: usize,
+    output_frames: usize,
+}
+
+struct ChannelConvolver {
+    history: Vec<f32>,
+    spectra: VecDeque<Vec<Complex<f32>>>,
+}
+
+impl Convolver {
+    /// Creates a convolver from a planar impulse response. When it has fewer
+    /// channels than the input they are reused in order, so a mono response
+    /// applies to every channel.
+    pub fn new(impulse_response: &[Vec<f32>], channels: usize, mix: f32) -> Self {
+        let mut planner = FftPlanner::new();
+        let forward = planner.plan_fft_forward(2 * BLOCK_SIZE);
+        let inverse = planner.plan_fft_inverse(2 * BLOCK_SIZE);
+
+        let partitions: Vec<Vec<Vec<Complex<f32>>>> = impulse_response
+            .iter()
+            .map(|response| {
+                response
+                    .chunks(BLOCK_SIZE)
+                    .map(|chunk| {
+                        let mut spectrum = vec![Complex::new(0f32, 0f32); 2 * BLOCK_SIZE];
+
+                        for (value, sample) in spectrum.iter_mut().zip(chunk) {
+                            value.re = *sample;
+                        }
+
+                        forward.process(&mut spectrum);
+                        spectrum
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let length = impulse_response.iter().map(|r| r.len()).max().unwrap_or(0);
+
+        return Self {
+            channels: (0..channels).map(|_| ChannelConvolver::new()).collect(),
+            partitions,
+            forward,
+            inverse,
+            mix,
+            pending: Vec::new(),
+            tail_frames: length.saturating_sub(1),
+            input_frames: 0,
+            output_frames: 0,
+        };
+    }
+
+    pub fn process(&mut self, data: &[f32]) -> Vec<f32> {
+        let channels = self.channels.len();
+        self.input_frames += data.len() / channels;
+        self.pending.extend_from_slice(data);
+
+        let mut output = Vec::new();
+
+        while self.pending.len() >= BLOCK_SIZE * channels {
+            let block: Vec<f32> = self.pending.drain(..BLOCK_SIZE * channels).collect();
+            output.extend(self.process_block(&block));
+        }
+
+        self.output_frames += output.len() / channels;
+
+        return output;
+    }
+
+    /// Processes the remaining input followed by the reverb tail. Should only
+    /// be called once all the input has been processed.
+    pub fn flush(&mut self) -> Vec<f32> {
+        let channels = self.channels.len();
+        let total_frames = self.input_frames + self.tail_frames;
+
+        let mut output = Vec::new();
+
+        while self.output_frames + output.len() / channels < total_frames {
+            let mut block = std::mem::take(&mut self.pending);
+            block.resize(BLOCK_SIZE * channels, 0f32);
+
+            output.extend(self.process_block(&block));
+        }
+
+        output.truncate(total_frames.saturating_sub(self.output_frames) * channels);
+        self.output_frames += output.len() / channels;
+
+        return output;
+    }
+
+    fn process_block(&mut self, block: &[f32]) -> Vec<f32> {
+        let channel_count = self.channels.len();
+        let mut output = vec![0f32; block.len()];
+
+        for (channel, state) in self.channels.iter_mut().enumerate() {
+            let partitions = &self.partitions[channel % self.partitions.len()];
+
+            // Keep the previous block around so the circular convolution
+            // wraps into samples that get discarded
+            state.history.copy_within(BLOCK_SIZE.., 0);
+            for (frame, sample) in state.history[BLOCK_SIZE..].iter_mut().enumerate() {
+                *sample = block[frame * channel_count + channel];
+            }
+
+            let mut spectrum: Vec<Complex<f32>> = state
+                .history
+                .iter()
+                .map(|sample| Complex::new(*sample, 0f32))
+                .collect();
+
+            self.forward.process(&mut spectrum);
+
+            state.spectra.push_front(spectrum);
+            state.spectra.truncate(partitions.len());
+
+            let mut accumulator = vec![Complex::new(0f32, 0f32); 2 * BLOCK_SIZE];
+
+            for (input, partition) in state.spectra.iter().zip(partitions) {
+                for ((value, a), b) in accumulator.iter_mut().zip(input).zip(partition) {
+                    *value += a * b;
+                }
+            }
+
+            self.inverse.process(&mut accumulator);
+
+            let scale = 1.0 / (2 * BLOCK_SIZE) as f32;
+
+            for (frame, value) in accumulator[BLOCK_SIZE..].iter().enumerate() {
+                let index = frame * channel_count + channel;
+                let wet = value.re * scale;
+
+                output[index] = block[index] * (1.0 - self.mix) + wet * self.mix;
+            }
+        }
+
+        return output;
+    }
+}
+
+impl ChannelConvolver {
+    fn new() -> Self {
+        return Self {
+            history: vec![0f32; 2 * BLOCK_SIZE],
+            spectra: VecDeque::new(),
+        };
+    }
+}
+
+/// Scales an impulse response so its loudest channel has unit energy, which
+/// keeps the wet signal at a level comparable to the dry one
+pub fn normalize(impulse_response: &mut [Vec<f32>]) {
+    let energy = impulse_response
+        .iter()
+        .map(|response| response.iter().map(|s| s * s).sum::<f32>())
+        .fold(0f32, f32::max);
+
+    if energy <= 0f32 {
+        return;
+    }
+
+    let gain = 1.0 / energy.sqrt();
+
+    for response in impulse_response.iter_mut() {
+        for sample in response.iter_mut() {
+            *sample *= gain;
+        }
+    }
+}
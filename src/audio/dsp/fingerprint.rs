@@ -0,0 +1,236 @@
+use rustfft::num_complex::Complex;
+use rustfft::FftPlanner;
+
+/// Sample rate the audio is converted to before fingerprinting
+pub const SAMPLE_RATE: u32 = 11025;
+
+const FRAME_SIZE: usize = 4096;
+const HOP_SIZE: usize = FRAME_SIZE / 8;
+
+const MIN_FREQUENCY: f32 = 28.0;
+const MAX_FREQUENCY: f32 = 3520.0;
+
+/// Frames quieter than this RMS level, about -60 dBFS, are left out since
+/// silence has no pitch to compare
+const SILENCE_THRESHOLD: f32 = 0.001;
+
+/// How many frames two fingerprints may be shifted by when comparing them
+const MAX_OFFSET: isize = 20;
+
+pub struct Fingerprint {
+    pub duration: f32,
+    pub values: Vec<u32>,
+}
+
+impl Fingerprint {
+    /// Computes the fingerprint of mono audio sampled at `SAMPLE_RATE`.
+    ///
+    /// Each frame is reduced to a 12 bin chroma vector, and every 32 bit value
+    /// encodes how neighbouring pitch classes and consecutive frames compare,
+    /// which survives re-encoding and volume changes. Audio shorter than a
+    /// frame is padded with silence, and silent frames are skipped.
+    pub fn from_samples(samples: &[f32]) -> Self {
+        let duration = samples.len() as f32 / SAMPLE_RATE as f32;
+
+        let padded;
+        let samples = if samples.len() < FRAME_SIZE {
+            padded = [samples, &vec![0f32; FRAME_SIZE - samples.len()]].concat();
+            &padded
+        } else {
+            samples
+        };
+
+        let chroma_classes = get_chroma_classes();
+        let window = get_hann_window();
+        let fft = FftPlanner::<f32>::new().plan_fft_forward(FRAME_SIZE);
+
+        let mut values = Vec::new();
+        let mut previous_chroma = [0f32; 12];
+        let mut start = 0;
+
+        while start + FRAME_SIZE <= samples.len() {
+            let frame = &samples[start..start + FRAME_SIZE];
+            start += HOP_SIZE;
+
+            let mean_square =
+                frame.iter().map(|sample| sample * sample).sum::<f32>() / FRAME_SIZE as f32;
+
+            if mean_square < SILENCE_THRESHOLD * SILENCE_THRESHOLD {
+                continue;
+            }
+
+            let mut spectrum: Vec<Complex<f32>> = frame
+                .iter()
+                .zip(window.iter())
+                .map(|(sample, weight)| Complex::new(sample * weight, 0f32))
+                .collect();
+
+            fft.process(&mut spectrum);
+
+            let mut chroma = [0f32; 12];
+
+            for (bin, class) in chroma_classes.iter().enumerate() {
+                if let Some(class) = class {
+                    chroma[*class] += spectrum[bin].norm_sqr();
+                }
+            }
+
+            normalize(&mut chroma);
+
+            values.push(get_frame_value(&chroma, &previous_chroma));
+            previous_chroma = chroma;
+        }
+
+        return Self { duration, values };
+    }
+
+    /// Similarity between 0.0 and 1.0 based on the share of matching bits,
+    /// using the best alignment of both fingerprints
+    pub fn similarity(&self, other: &Fingerprint) -> f32 {
+        let shortest = self.values.len().min(other.values.len());
+
+        if shortest == 0 {
+            return 0f32;
+        }
+
+        let mut best = 0f32;
+
+        for offset in -MAX_OFFSET..=MAX_OFFSET {
+            let pairs = self
+                .values
+                .iter()
+                .enumerate()
+                .filter_map(|(i, value)| {
+                    let j = i as isize + offset;
+
+                    if j < 0 {
+                        return None;
+                    }
+
+                    other.values.get(j as usize).map(|other| (value, other))
+                })
+                .collect::<Vec<_>>();
+
+            // Ignore alignments that only compare a small part of the audio
+            if pairs.len() * 2 < shortest {
+                continue;
+            }
+
+            let different_bits: u32 = pairs.iter().map(|(a, b)| (*a ^ *b).count_ones()).sum();
+            let score = 1f32 - different_bits as f32 / (pairs.len() * 32) as f32;
+
+            best = best.max(score);
+        }
+
+        return best;
+    }
+
+    pub fn to_hex(&self) -> String {
+        return self
+            .values
+            .iter()
+            .map(|value| format!("{:08x}", value))
+            .collect();
+    }
+}
+
+fn get_frame_value(chroma: &[f32; 12], previous_chroma: &[f32; 12]) -> u32 {
+    let mut value = 0u32;
+
+    for class in 0..12 {
+        if chroma[class] > chroma[(class + 1) % 12] {
+            value |= 1 << class;
+        }
+
+        if chroma[class] > previous_chroma[class] {
+            value |= 1 << (12 + class);
+        }
+    }
+
+    for class in 0..8 {
+        if chroma[class] + chroma[class + 1] > chroma[class + 2] + chroma[class + 3] {
+            value |= 1 << (24 + class);
+        }
+    }
+
+    return value;
+}
+
+/// Maps every fft bin to the pitch class it belongs to, if it is in range
+fn get_chroma_classes() -> Vec<Option<usize>> {
+    return (0..FRAME_SIZE / 2)
+        .map(|bin| {
+            let frequency = bin as f32 * SAMPLE_RATE as f32 / FRAME_SIZE as f32;
+
+            if !(MIN_FREQUENCY..=MAX_FREQUENCY).contains(&frequency) {
+                return None;
+            }
+
+            let note = 12f32 * (frequency / 440f32).log2() + 69f32;
+            return Some((note.round() as i32).rem_euclid(12) as usize);
+        })
+        .collect();
+}
+
+fn get_hann_window() -> Vec<f32> {
+    return (0..FRAME_SIZE)
+        .map(|i| 0.5 - 0.5 * (2f32 * std::f32::consts::PI * i as f32 / FRAME_SIZE as f32).cos())
+        .collect();
+}
+
+fn normalize(values: &mut [f32]) {
+    let norm = values.iter().map(|v| v * v).sum::<f32>().sqrt();
+
+    if norm > f32::EPSILON {
+        values.iter_mut().for_each(|v| *v /= norm);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone(frequencies: &[f32], seconds: f32) -> Vec<f32> {
+        return (0..(seconds * SAMPLE_RATE as f32) as usize)
+            .map(|i| {
+                let time = i as f32 / SAMPLE_RATE as f32;
+                frequencies
+                    .iter()
+                    .map(|frequency| (2.0 * std::f32::consts::PI * frequency * time).sin())
+                    .sum::<f32>()
+                    / frequencies.len() as f32
+            })
+            .collect();
+    }
+
+    #[test]
+    fn short_input_is_fingerprinted() {
+        let clip = tone(&[440.0, 660.0], 0.2);
+        let louder: Vec<f32> = clip.iter().map(|sample| sample * 1.5).collect();
+
+        let fingerprint = Fingerprint::from_samples(&clip);
+
+        assert!(!fingerprint.values.is_empty());
+        assert_eq!(
+            fingerprint.similarity(&Fingerprint::from_samples(&louder)),
+            1.0
+        );
+    }
+
+    #[test]
+    fn silence_does_not_match() {
+        let silence = vec![0f32; SAMPLE_RATE as usize * 10];
+
+        let mut first = tone(&[440.0, 554.4, 659.3], 1.0);
+        first.extend(&silence);
+
+        let mut second = tone(&[293.7, 370.0, 392.0], 1.0);
+        second.extend(&silence);
+
+        let similarity =
+            Fingerprint::from_samples(&first).similarity(&Fingerprint::from_samples(&second));
+
+        assert!(similarity < 0.85, "similarity: {}", similarity);
+        assert!(Fingerprint::from_samples(&silence).values.is_empty());
+    }
+}
@@ -0,0 +1,5 @@
+pub mod convolve;
+pub mod fingerprint;
+pub mod pitch;
+pub mod resample;
+pub mod tempo;
@@ -0,0 +1,250 @@
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+use rustfft::num_complex::Complex;
+use rustfft::{Fft, FftPlanner};
+
+const FRAME_SIZE: usize = 2048;
+
+/// Number of overlapping frames, higher values reduce phasing artifacts
+const OVERSAMPLING: usize = 4;
+const HOP_SIZE: usize = FRAME_SIZE / OVERSAMPLING;
+const LATENCY: usize = FRAME_SIZE - HOP_SIZE;
+
+/// Streaming phase vocoder that transposes interleaved audio without
+/// changing its duration. Each frame's bins are moved to their transposed
+/// frequency while keeping phases coherent between frames.
+pub struct PitchShifter {
+    channels: Vec<ChannelShifter>,
+    forward: Arc<dyn Fft<f32>>,
+    inverse: Arc<dyn Fft<f32>>,
+    window: Vec<f32>,
+    factor: f32,
+    bin_frequency: f32,
+    skipped: usize,
+    input_frames: usize,
+    output_frames: usize,
+}
+
+struct ChannelShifter {
+    input: Vec<f32>,
+    output: Vec<f32>,
+    accumulator: Vec<f32>,
+    last_phase: Vec<f32>,
+    phase_sum: Vec<f32>,
+    position: usize,
+}
+
+impl PitchShifter {
+    pub fn new(factor: f32, sample_rate: u32, channels: usize) -> Self {
+        let mut planner = FftPlanner::new();
+
+        return Self {
+            channels: (0..channels).map(|_| ChannelShifter::new()).collect(),
+            forward: planner.plan_fft_forward(FRAME_SIZE),
+            inverse: planner.plan_fft_inverse(FRAME_SIZE),
+            window: (0..FRAME_SIZE)
+                .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / FRAME_SIZE as f32).cos())
+                .collect(),
+            factor,
+            bin_frequency: sample_rate as f32 / FRAME_SIZE as f32,
+            skipped: 0,
+            input_frames: 0,
+            output_frames: 0,
+        };
+    }
+
+    pub fn process(&mut self, data: &[f32]) -> Vec<f32> {
+        let channels = self.channels.len();
+        let frames = data.len() / channels;
+        self.input_frames += frames;
+
+        let mut output = vec![0f32; frames * channels];
+
+        for frame in 0..frames {
+            for channel in 0..channels {
+                let sample = data[frame * channels + channel];
+                output[frame * channels + channel] = self.process_sample(channel, sample);
+            }
+        }
+
+        // The first frames only hold the vocoder latency
+        let skip = (LATENCY - self.skipped).min(frames);
+        self.skipped += skip;
+        self.output_frames += frames - skip;
+
+        return output.split_off(skip * channels);
+    }
+
+    /// Pushes silence through the vocoder to get the remaining samples out.
+    /// Should only be called once all the input has been processed.
+    pub fn flush(&mut self) -> Vec<f32> {
+        let channels = self.channels.len();
+        let remaining = self.input_frames - self.output_frames;
+
+        let mut output = self.process(&vec![0f32; LATENCY * channels]);
+        output.truncate(remaining * channels);
+
+        return output;
+    }
+
+    fn process_sample(&mut self, channel: usize, sample: f32) -> f32 {
+        let state = &mut self.channels[channel];
+
+        state.input[state.position] = sample;
+        let output = state.output[state.position - LATENCY];
+        state.position += 1;
+
+        if state.position >= FRAME_SIZE {
+            state.position = LATENCY;
+            self.process_frame(channel);
+        }
+
+        return output;
+    }
+
+    fn process_frame(&mut self, channel: usize) {
+        let half = FRAME_SIZE / 2;
+        let expected_phase = 2.0 * PI * HOP_SIZE as f32 / FRAME_SIZE as f32;
+        let state = &mut self.channels[channel];
+
+        let mut spectrum: Vec<Complex<f32>> = state
+            .input
+            .iter()
+            .zip(self.window.iter())
+            .map(|(sample, weight)| Complex::new(sample * weight, 0.0))
+            .collect();
+
+        self.forward.process(&mut spectrum);
+
+        // Analysis: find the true frequency of every bin from its phase advance
+        let mut magnitudes = vec![0f32; half + 1];
+        let mut frequencies = vec![0f32; half + 1];
+
+        for bin in 0..=half {
+            let magnitude = 2.0 * spectrum[bin].norm();
+            let phase = spectrum[bin].arg();
+
+            let mut delta = phase - state.last_phase[bin] - bin as f32 * expected_phase;
+            state.last_phase[bin] = phase;
+            delta -= 2.0 * PI * (delta / (2.0 * PI)).round();
+
+            let deviation = OVERSAMPLING as f32 * delta / (2.0 * PI);
+
+            magnitudes[bin] = magnitude;
+            frequencies[bin] = (bin as f32 + deviation) * self.bin_frequency;
+        }
+
+        // Processing: move every bin to its transposed position
+        let mut shifted_magnitudes = vec![0f32; half + 1];
+        let mut shifted_frequencies = vec![0f32; half + 1];
+
+        for bin in 0..=half {
+            let target = (bin as f32 * self.factor).round() as usize;
+
+            if target <= half {
+                shifted_magnitudes[target] += magnitudes[bin];
+                shifted_frequencies[target] = frequencies[bin] * self.factor;
+            }
+        }
+
+        // Synthesis: accumulate the phase of every bin from its new frequency
+        for bin in 0..FRAME_SIZE {
+            if bin > half {
+                spectrum[bin] = Complex::new(0.0, 0.0);
+                continue;
+            }
+
+            let deviation = shifted_frequencies[bin] / self.bin_frequency - bin as f32;
+            let advance = 2.0 * PI * deviation / OVERSAMPLING as f32 + bin as f32 * expected_phase;
+
+            // Kept within (-π, π] so the sum does not lose precision as it grows
+            state.phase_sum[bin] = (state.phase_sum[bin] + advance + PI).rem_euclid(2.0 * PI) - PI;
+
+            spectrum[bin] = Complex::from_polar(shifted_magnitudes[bin], state.phase_sum[bin]);
+        }
+
+        self.inverse.process(&mut spectrum);
+
+        let scale = 2.0 / (half * OVERSAMPLING) as f32;
+
+        for (i, value) in spectrum.iter().enumerate() {
+            state.accumulator[i] += self.window[i] * value.re * scale;
+        }
+
+        state.output[..HOP_SIZE].copy_from_slice(&state.accumulator[..HOP_SIZE]);
+
+        state.accumulator.copy_within(HOP_SIZE.., 0);
+        state.accumulator[FRAME_SIZE..].fill(0.0);
+        state.input.copy_within(HOP_SIZE.., 0);
+    }
+}
+
+impl ChannelShifter {
+    fn new() -> Self {
+        return Self {
+            input: vec![0f32; FRAME_SIZE],
+            output: vec![0f32; FRAME_SIZE],
+            accumulator: vec![0f32; 2 * FRAME_SIZE],
+            last_phase: vec![0f32; FRAME_SIZE / 2 + 1],
+            phase_sum: vec![0f32; FRAME_SIZE / 2 + 1],
+            position: LATENCY,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: u32 = 44100;
+
+    /// Amplitude of a frequency in a block of samples
+    fn amplitude_at(samples: &[f32], frequency: f32) -> f32 {
+        let step = 2.0 * std::f64::consts::PI * frequency as f64 / SAMPLE_RATE as f64;
+        let (mut real, mut imaginary) = (0f64, 0f64);
+
+        for (i, sample) in samples.iter().enumerate() {
+            real += *sample as f64 * (step * i as f64).cos();
+            imaginary += *sample as f64 * (step * i as f64).sin();
+        }
+
+        return (2.0 * (real * real + imaginary * imaginary).sqrt() / samples.len() as f64) as f32;
+    }
+
+    #[test]
+    fn tone_keeps_its_level() {
+        let frequency = 9000.0;
+        let factor = 2f32.powf(1.0 / 12.0);
+        let seconds = 5;
+
+        let input: Vec<f32> = (0..SAMPLE_RATE as usize * seconds)
+            .map(|i| {
+                let phase = 2.0 * std::f64::consts::PI * frequency * i as f64;
+                (0.5 * (phase / SAMPLE_RATE as f64).sin()) as f32
+            })
+            .collect();
+
+        let mut shifter = PitchShifter::new(factor, SAMPLE_RATE, 1);
+        let mut output = shifter.process(&input);
+        output.extend(shifter.flush());
+
+        assert_eq!(output.len(), input.len());
+
+        let block = SAMPLE_RATE as usize / 2;
+        let start = &output[2 * SAMPLE_RATE as usize..][..block];
+        let end = &output[output.len() - 2 * block..][..block];
+
+        let target = frequency as f32 * factor;
+        let start_level = amplitude_at(start, target);
+        let end_level = amplitude_at(end, target);
+
+        assert!(start_level > 0.3, "level at the start: {}", start_level);
+        assert!(
+            end_level > 0.9 * start_level,
+            "level dropped from {} to {}",
+            start_level,
+            end_level
+        );
+    }
+}
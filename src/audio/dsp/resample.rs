@@ -0,0 +1,110 @@
+use std::f64::consts::PI;
+
+/// Number of zero crossings of the sinc kernel on each side of the center
+const HALF_TAPS: usize = 16;
+
+/// Streaming windowed-sinc resampler working on interleaved samples
+pub struct Resampler {
+    channels: usize,
+    step: f64,
+    cutoff: f64,
+    half_width: usize,
+    buffer: Vec<f32>,
+    position: f64,
+    input_frames: u64,
+    output_frames: u64,
+}
+
+impl Resampler {
+    pub fn new(input_rate: u32, output_rate: u32, channels: usize) -> Self {
+        let step = input_rate as f64 / output_rate as f64;
+
+        // When downsampling the cutoff has to drop to the new nyquist frequency
+        let cutoff = (output_rate as f64 / input_rate as f64).min(1.0);
+        let half_width = (HALF_TAPS as f64 / cutoff).ceil() as usize;
+
+        return Self {
+            channels,
+            step,
+            cutoff,
+            half_width,
+            buffer: vec![0f32; half_width * channels],
+            position: half_width as f64,
+            input_frames: 0,
+            output_frames: 0,
+        };
+    }
+
+    pub fn process(&mut self, data: &[f32]) -> Vec<f32> {
+        self.input_frames += (data.len() / self.channels) as u64;
+        self.buffer.extend_from_slice(data);
+
+        return self.drain_available();
+    }
+
+    /// Resamples whatever is left in the buffer. Should only be called once
+    /// all the input has been processed.
+    pub fn flush(&mut self) -> Vec<f32> {
+        let expected_frames = (self.input_frames as f64 / self.step).ceil() as u64;
+        let remaining_frames = expected_frames.saturating_sub(self.output_frames);
+
+        self.buffer
+            .resize(self.buffer.len() + self.half_width * self.channels, 0f32);
+
+        let mut output = self.drain_available();
+        output.truncate(remaining_frames as usize * self.channels);
+
+        return output;
+    }
+
+    fn drain_available(&mut self) -> Vec<f32> {
+        let frames = self.buffer.len() / self.channels;
+        let mut output = Vec::new();
+
+        while (self.position.floor() as usize) + self.half_width < frames {
+            let center = self.position.floor() as usize;
+            let fraction = self.position - center as f64;
+
+            for channel in 0..self.channels {
+                let mut sample = 0f64;
+
+                for offset in 0..(2 * self.half_width) {
+                    let index = center + offset + 1 - self.half_width;
+                    let distance = (offset + 1) as f64 - self.half_width as f64 - fraction;
+
+                    sample +=
+                        self.buffer[index * self.channels + channel] as f64 * self.kernel(distance);
+                }
+
+                output.push(sample as f32);
+            }
+
+            self.position += self.step;
+            self.output_frames += 1;
+        }
+
+        // Keep only the frames still needed by the next output sample
+        let consumed = (self.position.floor() as usize + 1).saturating_sub(self.half_width);
+        self.buffer
+            .drain(..(consumed * self.channels).min(self.buffer.len()));
+        self.position -= consumed as f64;
+
+        return output;
+    }
+
+    fn kernel(&self, distance: f64) -> f64 {
+        let x = distance * self.cutoff;
+        let sinc = if x.abs() < f64::EPSILON {
+            1.0
+        } else {
+            (PI * x).sin() / (PI * x)
+        };
+
+        // Blackman window over the width of the kernel
+        let window_position = (distance / self.half_width as f64 + 1.0) / 2.0;
+        let window = 0.42 - 0.5 * (2.0 * PI * window_position).cos()
+            + 0.08 * (4.0 * PI * window_position).cos();
+
+        return self.cutoff * sinc * window;
+    }
+}
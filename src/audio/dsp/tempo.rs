@@ -0,0 +1,178 @@
+use rustfft::num_complex::Complex;
+use rustfft::FftPlanner;
+
+/// Sample rate the audio is converted to before analysis
+pub const SAMPLE_RATE: u32 = 11025;
+
+const FRAME_SIZE: usize = 1024;
+const HOP_SIZE: usize = 256;
+
+/// Window, in frames, used to remove the local average of the onset strength
+const MEAN_WINDOW: usize = 16;
+
+/// Tempo most likely to be perceived, used to break ties between multiples
+const PRIOR_BPM: f32 = 120.0;
+
+/// How strongly the beat tracker sticks to the estimated tempo
+const TIGHTNESS: f32 = 100.0;
+
+pub struct TempoEstimate {
+    pub bpm: f32,
+    pub beats: Vec<f32>,
+}
+
+/// Estimates the tempo of mono audio sampled at `SAMPLE_RATE` by
+/// autocorrelating its onset strength, then places beats on the strongest
+/// onsets that follow that tempo.
+pub fn estimate_tempo(samples: &[f32], min_bpm: f32, max_bpm: f32) -> Option<TempoEstimate> {
+    let onsets = get_onset_strength(samples);
+    let frame_rate = SAMPLE_RATE as f32 / HOP_SIZE as f32;
+
+    let min_lag = (60.0 * frame_rate / max_bpm).floor().max(1.0) as usize;
+    let max_lag = (60.0 * frame_rate / min_bpm).ceil() as usize;
+
+    if onsets.len() <= max_lag + 1 || min_lag >= max_lag {
+        return None;
+    }
+
+    let correlation: Vec<f32> = (0..=max_lag + 1)
+        .map(|lag| {
+            onsets
+                .iter()
+                .zip(onsets.iter().skip(lag))
+                .map(|(a, b)| a * b)
+                .sum::<f32>()
+        })
+        .collect();
+
+    // Weight each lag by a log-normal prior around the most common tempo
+    let weighted = |lag: usize| {
+        let bpm = 60.0 * frame_rate / lag as f32;
+        let octaves = (bpm / PRIOR_BPM).log2();
+        return correlation[lag] * (-0.5 * octaves * octaves).exp();
+    };
+
+    let best_lag = (min_lag..=max_lag).max_by(|a, b| weighted(*a).total_cmp(&weighted(*b)))?;
+
+    // Parabolic interpolation around the peak for a sub-frame period
+    let (left, center, right) = (
+        correlation[best_lag - 1],
+        correlation[best_lag],
+        correlation[best_lag + 1],
+    );
+    let denominator = left - 2.0 * center + right;
+    let shift = if denominator.abs() > f32::EPSILON {
+        (0.5 * (left - right) / denominator).clamp(-0.5, 0.5)
+    } else {
+        0.0
+    };
+
+    let period = best_lag as f32 + shift;
+    // Frames are reported at their center, where onsets have the most weight
+    let beats = track_beats(&onsets, period)
+        .into_iter()
+        .map(|frame| (frame * HOP_SIZE + FRAME_SIZE / 2) as f32 / SAMPLE_RATE as f32)
+        .collect();
+
+    return Some(TempoEstimate {
+        bpm: 60.0 * frame_rate / period,
+        beats,
+    });
+}
+
+/// Spectral flux of the log magnitude spectrum, with the local average
+/// removed so only sudden increases in energy remain
+fn get_onset_strength(samples: &[f32]) -> Vec<f32> {
+    let fft = FftPlanner::<f32>::new().plan_fft_forward(FRAME_SIZE);
+    let window: Vec<f32> = (0..FRAME_SIZE)
+        .map(|i| 0.5 - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / FRAME_SIZE as f32).cos())
+        .collect();
+
+    let mut flux = Vec::new();
+    let mut previous_spectrum = vec![0f32; FRAME_SIZE / 2];
+    let mut start = 0;
+
+    while start + FRAME_SIZE <= samples.len() {
+        let mut buffer: Vec<Complex<f32>> = samples[start..start + FRAME_SIZE]
+            .iter()
+            .zip(window.iter())
+            .map(|(sample, weight)| Complex::new(sample * weight, 0.0))
+            .collect();
+
+        fft.process(&mut buffer);
+
+        let spectrum: Vec<f32> = buffer[..FRAME_SIZE / 2]
+            .iter()
+            .map(|bin| (1.0 + 100.0 * bin.norm()).ln())
+            .collect();
+
+        flux.push(
+            spectrum
+                .iter()
+                .zip(previous_spectrum.iter())
+                .map(|(current, previous)| (current - previous).max(0.0))
+                .sum::<f32>(),
+        );
+
+        previous_spectrum = spectrum;
+        start += HOP_SIZE;
+    }
+
+    // The first frame compares against silence and would always be an onset
+    if let Some(first) = flux.first_mut() {
+        *first = 0.0;
+    }
+
+    return (0..flux.len())
+        .map(|i| {
+            let from = i.saturating_sub(MEAN_WINDOW / 2);
+            let to = (i + MEAN_WINDOW / 2).min(flux.len());
+            let mean = flux[from..to].iter().sum::<f32>() / (to - from) as f32;
+
+            (flux[i] - mean).max(0.0)
+        })
+        .collect();
+}
+
+/// Dynamic programming beat tracker, returning the frames holding a beat
+fn track_beats(onsets: &[f32], period: f32) -> Vec<usize> {
+    let mut scores = onsets.to_vec();
+    let mut previous: Vec<Option<usize>> = vec![None; onsets.len()];
+
+    let min_distance = (period / 2.0).round().max(1.0) as usize;
+    let max_distance = (period * 2.0).round() as usize;
+
+    for i in 0..onsets.len() {
+        let from = i.saturating_sub(max_distance);
+        let to = i.saturating_sub(min_distance);
+
+        let best = (from..to)
+            .map(|j| {
+                let deviation = ((i - j) as f32 / period).ln();
+                (j, scores[j] - TIGHTNESS * deviation * deviation)
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+
+        if let Some((j, score)) = best {
+            if score > 0.0 {
+                scores[i] += score;
+                previous[i] = Some(j);
+            }
+        }
+    }
+
+    // Start from the best score within the last beat period
+    let last_period = onsets.len().saturating_sub(period.round() as usize);
+    let mut current = (last_period..onsets.len()).max_by(|a, b| scores[*a].total_cmp(&scores[*b]));
+
+    let mut beats = Vec::new();
+
+    while let Some(frame) = current {
+        beats.push(frame);
+        current = previous[frame];
+    }
+
+    beats.reverse();
+
+    return beats;
+}
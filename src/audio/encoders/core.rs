@@ -1,35 +1,215 @@
-use std::path;
+use std::{fs, io};
 
-use super::{errors, mp3, ogg, wav};
+use clap::{Args, ValueEnum};
+
+#[cfg(feature = "opus")]
+use super::opus;
+use super::{dither, errors, mp3, ogg, wav};
+use crate::audio::registry::AudioFormat;
+use crate::audio::utils as audio_utils;
+use crate::internal::utils;
 
 pub trait Encode {
     fn encode(&mut self, data: &[f32]) -> Result<(), errors::Error>;
+
+    /// Writes whatever the encoder still holds once all the audio has been
+    /// encoded. Must be called before the output is considered complete.
+    fn finish(&mut self) -> Result<(), errors::Error> {
+        return Ok(());
+    }
+}
+
+#[derive(Args, Clone)]
+pub struct EncoderOptions {
+    /// Output format. Required when writing to stdout, otherwise inferred
+    /// from the output extension
+    #[clap(long)]
+    pub format: Option<AudioFormat>,
+
+    /// Target bitrate in kbps, between 6 and 510. Only used by the opus
+    /// encoder, which picks one automatically when not set
+    #[clap(long, value_parser = clap::value_parser!(u32).range(6..=510))]
+    pub bitrate: Option<u32>,
+
+    /// Kind of content being encoded. Only used by the opus encoder, which
+    /// defaults to audio
+    #[clap(long)]
+    pub application: Option<OpusApplication>,
+
+    /// Sample format of the output. Only used by the wav encoder, which
+    /// defaults to 32-bit float
+    #[clap(long)]
+    pub bit_depth: Option<BitDepth>,
+
+    /// Noise added when reducing the bit depth. Only used by 16 and 24-bit
+    /// wav, which default to tpdf
+    #[clap(long)]
+    pub dither: Option<Dither>,
+
+    /// Shape the dither noise towards frequencies the ear is less sensitive
+    /// to when reducing the bit depth. Only used by 16 and 24-bit wav
+    #[clap(long)]
+    pub noise_shaping: bool,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum OpusApplication {
+    /// Favors speech intelligibility
+    Voip,
+    /// Favors fidelity to the input
+    Audio,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum BitDepth {
+    /// 16-bit integer
+    #[value(name = "16")]
+    Int16,
+    /// 24-bit integer
+    #[value(name = "24")]
+    Int24,
+    /// 32-bit float
+    #[value(name = "32")]
+    Float32,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum Dither {
+    /// Triangular noise of one step peak, which removes truncation distortion
+    Tpdf,
+    /// Plain rounding
+    None,
 }
 
 pub fn get_encoder(
-    file_path: &path::Path,
+    output: &str,
+    options: &EncoderOptions,
+    channels: u16,
+    sample_rate: u32,
+) -> Result<Box<dyn Encode>, errors::Error> {
+    if audio_utils::is_standard_stream(output) {
+        let format = options.format.ok_or(errors::Error::MissingFormatError)?;
+        check_options(format, options)?;
+
+        let writer = io::BufWriter::new(io::stdout());
+
+        return get_stream_encoder(writer, format, options, channels, sample_rate);
+    }
+
+    let file_path = utils::to_absolute_path(output);
+    let format = match options.format {
+        Some(format) => format,
+        None => AudioFormat::from_path(&file_path)
+            .ok_or_else(|| errors::Error::UnknownFormatError(file_path.display().to_string()))?,
+    };
+
+    check_options(format, options)?;
+
+    let file = fs::File::create(&file_path).map_err(|e| errors::Error::IoError(e))?;
+
+    return match format {
+        AudioFormat::Wav => Ok(Box::new(
+            wav::WavEncoder::new(
+                io::BufWriter::new(file),
+                channels,
+                sample_rate,
+                get_quantizer(options, channels),
+            )
+            .map_err(|e| errors::Error::WavEncodeError(e))?,
+        )),
+        _ => get_stream_encoder(file, format, options, channels, sample_rate),
+    };
+}
+
+/// Creates an encoder that only needs a sequential writer, such as stdout
+fn get_stream_encoder<W: io::Write + 'static>(
+    writer: W,
+    format: AudioFormat,
+    options: &EncoderOptions,
     channels: u16,
     sample_rate: u32,
 ) -> Result<Box<dyn Encode>, errors::Error> {
-    if let Some(extension) = file_path.extension() {
-        if let Some(extension_str) = extension.to_str() {
-            return match extension_str {
-                "wav" => Ok(Box::new(
-                    wav::WavEncoder::new(file_path, channels, sample_rate)
-                        .map_err(|e| errors::Error::WavEncodeError(e))?,
-                )),
-                "mp3" => Ok(Box::new(
-                    mp3::Mp3Encoder::new(file_path, channels, sample_rate)
-                        .map_err(|e| errors::Error::Mp3EncodeError(e))?,
-                )),
-                "ogg" => Ok(Box::new(
-                    ogg::OggEncoder::new(file_path, channels, sample_rate)
-                        .map_err(|e| errors::Error::OggEncodeError(e))?,
-                )),
-                _ => Err(errors::Error::NotImplementedError),
-            };
+    return match format {
+        AudioFormat::Mp3 => Ok(Box::new(
+            mp3::Mp3Encoder::new(writer, channels, sample_rate)
+                .map_err(|e| errors::Error::Mp3EncodeError(e))?,
+        )),
+        AudioFormat::Ogg => Ok(Box::new(
+            ogg::OggEncoder::new(writer, channels, sample_rate)
+                .map_err(|e| errors::Error::OggEncodeError(e))?,
+        )),
+        AudioFormat::Wav => Ok(Box::new(
+            wav::WavStreamEncoder::new(
+                writer,
+                channels,
+                sample_rate,
+                get_quantizer(options, channels),
+            )
+            .map_err(|e| errors::Error::WavEncodeError(e))?,
+        )),
+        #[cfg(feature = "opus")]
+        AudioFormat::Opus => Ok(Box::new(
+            opus::OpusEncoder::new(
+                writer,
+                channels,
+                sample_rate,
+                options.bitrate,
+                options.application.unwrap_or(OpusApplication::Audio),
+            )
+            .map_err(|e| errors::Error::OpusEncodeError(e))?,
+        )),
+        #[cfg(not(feature = "opus"))]
+        AudioFormat::Opus => Err(errors::Error::FeatureDisabledError("opus")),
+    };
+}
+
+/// Rejects encoder options that the output format would silently ignore
+fn check_options(format: AudioFormat, options: &EncoderOptions) -> Result<(), errors::Error> {
+    if format != AudioFormat::Opus {
+        if options.bitrate.is_some() {
+            return Err(errors::Error::UnsupportedOptionError("--bitrate", "opus"));
+        }
+
+        if options.application.is_some() {
+            return Err(errors::Error::UnsupportedOptionError(
+                "--application",
+                "opus",
+            ));
         }
     }
 
-    return Err(errors::Error::NotImplementedError);
+    if format != AudioFormat::Wav && options.bit_depth.is_some() {
+        return Err(errors::Error::UnsupportedOptionError("--bit-depth", "wav"));
+    }
+
+    // Only integer samples are quantized
+    let is_quantized = format == AudioFormat::Wav
+        && matches!(options.bit_depth, Some(BitDepth::Int16 | BitDepth::Int24));
+
+    if !is_quantized {
+        if options.dither.is_some() {
+            return Err(errors::Error::UnsupportedOptionError(
+                "--dither",
+                "16 and 24-bit wav",
+            ));
+        }
+
+        if options.noise_shaping {
+            return Err(errors::Error::UnsupportedOptionError(
+                "--noise-shaping",
+                "16 and 24-bit wav",
+            ));
+        }
+    }
+
+    return Ok(());
+}
+
+fn get_quantizer(options: &EncoderOptions, channels: u16) -> Option<dither::Quantizer> {
+    return dither::Quantizer::new(
+        options.bit_depth.unwrap_or(BitDepth::Float32),
+        channels,
+        options.dither.unwrap_or(Dither::Tpdf),
+        options.noise_shaping,
+    );
 }
@@ -0,0 +1,102 @@
+use super::core::{BitDepth, Dither};
+
+/// Error feedback coefficients of a 3-tap F-weighted noise shaping filter,
+/// which moves the quantization noise away from the frequencies the ear is
+/// most sensitive to. Designed for 44.1 kHz but still useful at 48 kHz.
+const NOISE_SHAPING: [f32; 3] = [1.623, -0.982, 0.109];
+
+/// Converts f32 samples to integers of a lower bit depth, adding triangular
+/// dither so the quantization error is decorrelated from the signal instead
+/// of sounding like distortion
+pub struct Quantizer {
+    bits: u16,
+    dither: Dither,
+    noise_shaping: bool,
+    channels: usize,
+    errors: Vec<[f32; 3]>,
+    position: usize,
+    state: u32,
+}
+
+impl Quantizer {
+    /// Returns `None` for bit depths that are written as floats and do not
+    /// need quantizing
+    pub fn new(
+        bit_depth: BitDepth,
+        channels: u16,
+        dither: Dither,
+        noise_shaping: bool,
+    ) -> Option<Self> {
+        let bits = match bit_depth {
+            BitDepth::Int16 => 16,
+            BitDepth::Int24 => 24,
+            BitDepth::Float32 => return None,
+        };
+
+        return Some(Self {
+            bits,
+            dither,
+            noise_shaping,
+            channels: channels as usize,
+            errors: vec![[0f32; 3]; channels as usize],
+            position: 0,
+            state: 0x9E37_79B9,
+        });
+    }
+
+    pub fn bits(&self) -> u16 {
+        return self.bits;
+    }
+
+    pub fn quantize(&mut self, data: &[f32]) -> Vec<i32> {
+        let max = ((1i32 << (self.bits - 1)) - 1) as f32;
+        let min = -max - 1.0;
+
+        let mut output = Vec::with_capacity(data.len());
+
+        for sample in data.iter() {
+            let channel = self.position % self.channels;
+            self.position = (self.position + 1) % self.channels;
+
+            let mut value = sample * (max + 1.0);
+
+            if self.noise_shaping {
+                let errors = self.errors[channel];
+
+                value -= NOISE_SHAPING[0] * errors[0]
+                    + NOISE_SHAPING[1] * errors[1]
+                    + NOISE_SHAPING[2] * errors[2];
+            }
+
+            let noise = match self.dither {
+                Dither::Tpdf => self.next_uniform() - self.next_uniform(),
+                Dither::None => 0.0,
+            };
+
+            let quantized = (value + noise).round().clamp(min, max);
+
+            if self.noise_shaping {
+                let errors = &mut self.errors[channel];
+
+                // Clipped samples would feed back huge errors, so keep them
+                // within a few steps
+                errors.rotate_right(1);
+                errors[0] = (quantized - value).clamp(-4.0, 4.0);
+            }
+
+            output.push(quantized as i32);
+        }
+
+        return output;
+    }
+
+    /// Uniform random value in [0, 1) from a xorshift generator, which is
+    /// plenty for dither noise
+    fn next_uniform(&mut self) -> f32 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 17;
+        self.state ^= self.state << 5;
+
+        return (self.state >> 8) as f32 / (1u32 << 24) as f32;
+    }
+}
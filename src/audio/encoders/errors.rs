@@ -1,5 +1,7 @@
 use thiserror::Error;
 
+#[cfg(feature = "opus")]
+use super::opus;
 use super::{mp3, ogg, wav};
 
 #[derive(Debug, Error)]
@@ -10,6 +12,17 @@ pub enum Error {
     WavEncodeError(wav::WavEncodeError),
     #[error("{0}")]
     OggEncodeError(ogg::OggEncoderError),
-    #[error("Function not implemented")]
-    NotImplementedError,
+    #[cfg(feature = "opus")]
+    #[error("{0}")]
+    OpusEncodeError(opus::OpusEncodeError),
+    #[error("{0}")]
+    IoError(std::io::Error),
+    #[error("An output format is required when writing to stdout")]
+    MissingFormatError,
+    #[error("Could not infer the output format of {0}, use --format to set it")]
+    UnknownFormatError(String),
+    #[error("Encoding to {0} requires building with the \"{0}\" feature")]
+    FeatureDisabledError(&'static str),
+    #[error("{0} only applies to {1} output")]
+    UnsupportedOptionError(&'static str, &'static str),
 }
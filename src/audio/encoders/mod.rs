@@ -1,7 +1,10 @@
 pub mod core;
 
+mod dither;
 mod mp3;
 mod ogg;
+#[cfg(feature = "opus")]
+mod opus;
 mod wav;
 
 pub mod errors;
@@ -1,5 +1,4 @@
-use std::io::Write;
-use std::{fs, path};
+use std::io;
 
 use mp3lame_encoder;
 use thiserror::Error;
@@ -7,8 +6,8 @@ use thiserror::Error;
 use super::core::Encode;
 use super::errors;
 
-pub struct Mp3Encoder {
-    file: fs::File,
+pub struct Mp3Encoder<W: io::Write> {
+    output: W,
     writer: mp3lame_encoder::Encoder,
 }
 
@@ -20,15 +19,14 @@ pub enum Mp3EncodeError {
     Mp3EncodeError(mp3lame_encoder::EncodeError),
     #[error("{0}")]
     IoError(std::io::Error),
+    #[error("Could not initialize the mp3 encoder")]
+    InitializationError,
 }
 
-impl Mp3Encoder {
-    pub fn new(
-        filename: &path::Path,
-        channels: u16,
-        sample_rate: u32,
-    ) -> Result<Self, Mp3EncodeError> {
-        let mut builder = mp3lame_encoder::Builder::new().unwrap();
+impl<W: io::Write> Mp3Encoder<W> {
+    pub fn new(output: W, channels: u16, sample_rate: u32) -> Result<Self, Mp3EncodeError> {
+        let mut builder =
+            mp3lame_encoder::Builder::new().ok_or(Mp3EncodeError::InitializationError)?;
 
         builder
             .set_num_channels(channels as u8)
@@ -43,16 +41,15 @@ impl Mp3Encoder {
             .set_brate(mp3lame_encoder::Bitrate::Kbps192)
             .map_err(|e| Mp3EncodeError::Mp3EncoderBuilderError(e))?;
 
-        let file = fs::File::create(filename).map_err(|e| Mp3EncodeError::IoError(e))?;
+        let writer = builder
+            .build()
+            .map_err(|e| Mp3EncodeError::Mp3EncoderBuilderError(e))?;
 
-        return Ok(Self {
-            writer: builder.build().unwrap(),
-            file,
-        });
+        return Ok(Self { writer, output });
     }
 }
 
-impl Encode for Mp3Encoder {
+impl<W: io::Write> Encode for Mp3Encoder<W> {
     fn encode(&mut self, data: &[f32]) -> Result<(), errors::Error> {
         let interleaved_pcm = mp3lame_encoder::InterleavedPcm { 0: data };
 
@@ -70,8 +67,32 @@ impl Encode for Mp3Encoder {
             mp3_out_buffer.set_len(mp3_out_buffer.len().wrapping_add(encoded_size));
         }
 
-        self.file
-            .write(mp3_out_buffer.as_slice())
+        self.output
+            .write_all(mp3_out_buffer.as_slice())
+            .map_err(|e| errors::Error::Mp3EncodeError(Mp3EncodeError::IoError(e)))?;
+
+        return Ok(());
+    }
+
+    /// Writes the frames LAME still buffers, which hold the end of the audio
+    fn finish(&mut self) -> Result<(), errors::Error> {
+        let mut mp3_out_buffer = Vec::<u8>::new();
+        mp3_out_buffer.reserve(mp3lame_encoder::max_required_buffer_size(0));
+
+        let encoded_size = self
+            .writer
+            .flush::<mp3lame_encoder::FlushNoGap>(mp3_out_buffer.spare_capacity_mut())
+            .map_err(|e| errors::Error::Mp3EncodeError(Mp3EncodeError::Mp3EncodeError(e)))?;
+
+        unsafe {
+            mp3_out_buffer.set_len(mp3_out_buffer.len().wrapping_add(encoded_size));
+        }
+
+        self.output
+            .write_all(mp3_out_buffer.as_slice())
+            .map_err(|e| errors::Error::Mp3EncodeError(Mp3EncodeError::IoError(e)))?;
+        self.output
+            .flush()
             .map_err(|e| errors::Error::Mp3EncodeError(Mp3EncodeError::IoError(e)))?;
 
         return Ok(());
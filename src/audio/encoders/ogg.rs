@@ -1,5 +1,5 @@
+use std::io;
 use std::num::{NonZeroU32, NonZeroU8};
-use std::{fs, path};
 
 use thiserror::Error;
 use vorbis_rs;
@@ -11,52 +11,81 @@ use crate::audio::utils;
 #[derive(Debug, Error)]
 pub enum OggEncoderError {
     #[error("{0}")]
-    IOError(std::io::Error),
+    VorbisError(vorbis_rs::VorbisError),
+
+    #[error("Invalid sample rate")]
+    InvalidSampleRateError,
+
+    #[error("Invalid number of channels")]
+    InvalidChannelsError,
 
     #[error("{0}")]
-    VorbisError(vorbis_rs::VorbisError),
+    IoError(std::io::Error),
+
+    #[error("The ogg encoder was already finished")]
+    FinishedError,
 }
 
-pub struct OggEncoder {
-    writer: vorbis_rs::VorbisEncoder<fs::File>,
+pub struct OggEncoder<W: io::Write> {
+    writer: Option<vorbis_rs::VorbisEncoder<W>>,
     channels: u16,
 }
 
-impl OggEncoder {
-    pub fn new(
-        file_path: &path::Path,
-        channels: u16,
-        sample_rate: u32,
-    ) -> Result<Self, OggEncoderError> {
-        let file = fs::File::create(file_path).map_err(|e| OggEncoderError::IOError(e))?;
+impl<W: io::Write> OggEncoder<W> {
+    pub fn new(output: W, channels: u16, sample_rate: u32) -> Result<Self, OggEncoderError> {
+        let sample_rate =
+            NonZeroU32::new(sample_rate).ok_or(OggEncoderError::InvalidSampleRateError)?;
+        let channels_count = u8::try_from(channels)
+            .ok()
+            .and_then(NonZeroU8::new)
+            .ok_or(OggEncoderError::InvalidChannelsError)?;
 
         let encoder = vorbis_rs::VorbisEncoder::new(
             0,
             [("", ""); 0],
-            NonZeroU32::new(sample_rate).unwrap(),
-            NonZeroU8::new(channels as u8).unwrap(),
+            sample_rate,
+            channels_count,
             vorbis_rs::VorbisBitrateManagementStrategy::QualityVbr {
                 target_quality: 1f32,
             },
             None,
-            file,
+            output,
         )
         .map_err(|e| OggEncoderError::VorbisError(e))?;
 
         return Ok(Self {
-            writer: encoder,
+            writer: Some(encoder),
             channels,
         });
     }
 }
 
-impl Encode for OggEncoder {
+impl<W: io::Write> Encode for OggEncoder<W> {
     fn encode(&mut self, data: &[f32]) -> Result<(), errors::Error> {
         let channels_data = utils::interleaved_to_planar(data, self.channels as usize);
 
         self.writer
+            .as_mut()
+            .ok_or(errors::Error::OggEncodeError(
+                OggEncoderError::FinishedError,
+            ))?
             .encode_audio_block(&channels_data)
             .map_err(|e| errors::Error::OggEncodeError(OggEncoderError::VorbisError(e)))?;
         return Ok(());
     }
+
+    /// Writes the end of the stream and flushes the output
+    fn finish(&mut self) -> Result<(), errors::Error> {
+        if let Some(writer) = self.writer.take() {
+            let mut output = writer
+                .finish()
+                .map_err(|e| errors::Error::OggEncodeError(OggEncoderError::VorbisError(e)))?;
+
+            output
+                .flush()
+                .map_err(|e| errors::Error::OggEncodeError(OggEncoderError::IoError(e)))?;
+        }
+
+        return Ok(());
+    }
 }
@@ -0,0 +1,264 @@
+use std::io;
+
+use audiopus::coder::Encoder;
+use audiopus::{Application, Bitrate, Channels, SampleRate};
+use log::error;
+use ogg::writing::{PacketWriteEndInfo, PacketWriter};
+use thiserror::Error;
+
+use super::core::{Encode, OpusApplication};
+use super::errors;
+use crate::audio::dsp::resample::Resampler;
+
+/// Opus always runs at 48 kHz, any other input rate is resampled
+const OPUS_SAMPLE_RATE: u32 = 48000;
+
+/// 20ms frames, the recommended default for both speech and music
+const FRAME_SIZE: usize = 960;
+
+/// Largest packet recommended by the libopus documentation
+const MAX_PACKET_SIZE: usize = 4000;
+
+const STREAM_SERIAL: u32 = 0;
+
+#[derive(Debug, Error)]
+pub enum OpusEncodeError {
+    #[error("{0}")]
+    OpusError(audiopus::Error),
+    #[error("{0}")]
+    IoError(std::io::Error),
+    #[error("Opus only supports mono and stereo audio")]
+    UnsupportedChannelsError,
+    #[error("Invalid sample rate")]
+    InvalidSampleRateError,
+}
+
+pub struct OpusEncoder<W: io::Write> {
+    encoder: Encoder,
+    writer: PacketWriter<W>,
+    resampler: Option<Resampler>,
+    channels: usize,
+    pending: Vec<f32>,
+    pre_skip: u64,
+    samples_written: u64,
+    granule_position: u64,
+    previous_packet: Option<(Vec<u8>, u64)>,
+    finished: bool,
+}
+
+impl<W: io::Write> OpusEncoder<W> {
+    pub fn new(
+        output: W,
+        channels: u16,
+        sample_rate: u32,
+        bitrate: Option<u32>,
+        application: OpusApplication,
+    ) -> Result<Self, OpusEncodeError> {
+        if sample_rate == 0 {
+            return Err(OpusEncodeError::InvalidSampleRateError);
+        }
+
+        let opus_channels = match channels {
+            1 => Channels::Mono,
+            2 => Channels::Stereo,
+            _ => return Err(OpusEncodeError::UnsupportedChannelsError),
+        };
+
+        let opus_application = match application {
+            OpusApplication::Voip => Application::Voip,
+            OpusApplication::Audio => Application::Audio,
+        };
+
+        let mut encoder = Encoder::new(SampleRate::Hz48000, opus_channels, opus_application)
+            .map_err(|e| OpusEncodeError::OpusError(e))?;
+
+        let opus_bitrate = match bitrate {
+            Some(kbps) => Bitrate::BitsPerSecond(kbps as i32 * 1000),
+            None => Bitrate::Auto,
+        };
+
+        encoder
+            .set_bitrate(opus_bitrate)
+            .map_err(|e| OpusEncodeError::OpusError(e))?;
+
+        let pre_skip = encoder
+            .lookahead()
+            .map_err(|e| OpusEncodeError::OpusError(e))?;
+
+        let resampler = if sample_rate != OPUS_SAMPLE_RATE {
+            Some(Resampler::new(
+                sample_rate,
+                OPUS_SAMPLE_RATE,
+                channels as usize,
+            ))
+        } else {
+            None
+        };
+
+        let mut opus_encoder = Self {
+            encoder,
+            writer: PacketWriter::new(output),
+            resampler,
+            channels: channels as usize,
+            pending: Vec::new(),
+            pre_skip: pre_skip as u64,
+            samples_written: 0,
+            granule_position: 0,
+            previous_packet: None,
+            finished: false,
+        };
+
+        opus_encoder.write_headers(sample_rate)?;
+
+        return Ok(opus_encoder);
+    }
+
+    /// Writes the identification and comment headers, each on its own page
+    /// as required by RFC 7845
+    fn write_headers(&mut self, input_sample_rate: u32) -> Result<(), OpusEncodeError> {
+        let mut head = Vec::with_capacity(19);
+        head.extend_from_slice(b"OpusHead");
+        head.push(1);
+        head.push(self.channels as u8);
+        head.extend_from_slice(&(self.pre_skip as u16).to_le_bytes());
+        head.extend_from_slice(&input_sample_rate.to_le_bytes());
+        head.extend_from_slice(&0i16.to_le_bytes());
+        head.push(0);
+
+        let vendor = concat!("media_tools ", env!("CARGO_PKG_VERSION")).as_bytes();
+        let mut tags = Vec::with_capacity(16 + vendor.len());
+        tags.extend_from_slice(b"OpusTags");
+        tags.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+        tags.extend_from_slice(vendor);
+        tags.extend_from_slice(&0u32.to_le_bytes());
+
+        for header in [head, tags] {
+            self.writer
+                .write_packet(
+                    header.into_boxed_slice(),
+                    STREAM_SERIAL,
+                    PacketWriteEndInfo::EndPage,
+                    0,
+                )
+                .map_err(|e| OpusEncodeError::IoError(e))?;
+        }
+
+        return Ok(());
+    }
+
+    fn encode_pending_frames(&mut self) -> Result<(), OpusEncodeError> {
+        let frame_length = FRAME_SIZE * self.channels;
+
+        while self.pending.len() >= frame_length {
+            let frame: Vec<f32> = self.pending.drain(..frame_length).collect();
+            let mut packet = vec![0u8; MAX_PACKET_SIZE];
+
+            let packet_size = self
+                .encoder
+                .encode_float(&frame, &mut packet)
+                .map_err(|e| OpusEncodeError::OpusError(e))?;
+            packet.truncate(packet_size);
+
+            self.granule_position += FRAME_SIZE as u64;
+
+            // Packets are written one step behind, so the last one can be
+            // flagged as the end of the stream
+            if let Some(previous) = self
+                .previous_packet
+                .replace((packet, self.granule_position))
+            {
+                self.write_packet(previous, PacketWriteEndInfo::NormalPacket)?;
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn write_packet(
+        &mut self,
+        (packet, granule_position): (Vec<u8>, u64),
+        end_info: PacketWriteEndInfo,
+    ) -> Result<(), OpusEncodeError> {
+        return self
+            .writer
+            .write_packet(
+                packet.into_boxed_slice(),
+                STREAM_SERIAL,
+                end_info,
+                granule_position,
+            )
+            .map_err(|e| OpusEncodeError::IoError(e));
+    }
+
+    /// Encodes the remaining samples and marks the end of the Ogg stream
+    fn end_stream(&mut self) -> Result<(), OpusEncodeError> {
+        if self.finished {
+            return Ok(());
+        }
+
+        self.finished = true;
+
+        if let Some(resampler) = self.resampler.as_mut() {
+            let remaining = resampler.flush();
+            self.samples_written += (remaining.len() / self.channels) as u64;
+            self.pending.extend(remaining);
+        }
+
+        // The encoder delays its output by the pre-skip, so that much silence
+        // has to be pushed through before padding up to a whole frame
+        let frame_length = FRAME_SIZE * self.channels;
+        let mut padding = self.pre_skip as usize * self.channels;
+        padding += (frame_length - (self.pending.len() + padding) % frame_length) % frame_length;
+
+        self.pending.resize(self.pending.len() + padding, 0f32);
+        self.encode_pending_frames()?;
+
+        if let Some((packet, _)) = self.previous_packet.take() {
+            // The final granule position marks where the decoded audio ends,
+            // trimming the padding added above
+            let end_position = self.pre_skip + self.samples_written;
+            self.write_packet((packet, end_position), PacketWriteEndInfo::EndStream)?;
+        }
+
+        return Ok(());
+    }
+}
+
+impl<W: io::Write> Encode for OpusEncoder<W> {
+    fn encode(&mut self, data: &[f32]) -> Result<(), errors::Error> {
+        let samples = match self.resampler.as_mut() {
+            Some(resampler) => resampler.process(data),
+            None => data.to_vec(),
+        };
+
+        self.samples_written += (samples.len() / self.channels) as u64;
+        self.pending.extend(samples);
+
+        self.encode_pending_frames()
+            .map_err(|e| errors::Error::OpusEncodeError(e))?;
+
+        return Ok(());
+    }
+
+    fn finish(&mut self) -> Result<(), errors::Error> {
+        self.end_stream()
+            .map_err(|e| errors::Error::OpusEncodeError(e))?;
+
+        self.writer
+            .inner_mut()
+            .flush()
+            .map_err(|e| errors::Error::OpusEncodeError(OpusEncodeError::IoError(e)))?;
+
+        return Ok(());
+    }
+}
+
+/// Streams that were not finished, because encoding stopped on an error,
+/// still get a valid ending
+impl<W: io::Write> Drop for OpusEncoder<W> {
+    fn drop(&mut self) {
+        if let Err(e) = self.end_stream() {
+            error!("Failed to finish opus stream: {}", e);
+        }
+    }
+}
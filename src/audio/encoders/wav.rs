@@ -1,50 +1,158 @@
-use std::{fs, io, path};
+use std::io;
 
 use hound;
 use thiserror::Error;
 
 use super::core::Encode;
+use super::dither::Quantizer;
 use super::errors;
 
 #[derive(Debug, Error)]
 pub enum WavEncodeError {
     #[error("{0}")]
     HoundError(hound::Error),
+    #[error("{0}")]
+    IoError(std::io::Error),
+    #[error("The wav encoder was already finished")]
+    FinishedError,
 }
 
-pub struct WavEncoder {
-    writer: hound::WavWriter<io::BufWriter<fs::File>>,
+/// Wav encoder writing 32-bit float samples, or integer samples when given
+/// a quantizer
+pub struct WavEncoder<W: io::Write + io::Seek> {
+    writer: Option<hound::WavWriter<W>>,
+    quantizer: Option<Quantizer>,
 }
 
-impl WavEncoder {
+impl<W: io::Write + io::Seek> WavEncoder<W> {
     pub fn new(
-        filename: &path::Path,
+        output: W,
         channels: u16,
         sample_rate: u32,
+        quantizer: Option<Quantizer>,
     ) -> Result<Self, WavEncodeError> {
-        let writer = hound::WavWriter::create(
-            filename,
-            hound::WavSpec {
-                sample_rate,
-                channels,
-                bits_per_sample: 32,
-                sample_format: hound::SampleFormat::Float,
-            },
-        )
-        .map_err(|e| WavEncodeError::HoundError(e))?;
-
-        return Ok(Self { writer });
+        let spec = get_wav_spec(channels, sample_rate, quantizer.as_ref());
+        let writer =
+            hound::WavWriter::new(output, spec).map_err(|e| WavEncodeError::HoundError(e))?;
+
+        return Ok(Self {
+            writer: Some(writer),
+            quantizer,
+        });
     }
 }
 
-impl Encode for WavEncoder {
+impl<W: io::Write + io::Seek> Encode for WavEncoder<W> {
     fn encode(&mut self, data: &[f32]) -> Result<(), errors::Error> {
-        for sample in data.iter() {
-            self.writer
-                .write_sample(*sample)
+        let writer = self
+            .writer
+            .as_mut()
+            .ok_or(errors::Error::WavEncodeError(WavEncodeError::FinishedError))?;
+
+        match self.quantizer.as_mut() {
+            Some(quantizer) => {
+                for sample in quantizer.quantize(data) {
+                    writer.write_sample(sample).map_err(|e| {
+                        errors::Error::WavEncodeError(WavEncodeError::HoundError(e))
+                    })?;
+                }
+            }
+            None => {
+                for sample in data.iter() {
+                    writer.write_sample(*sample).map_err(|e| {
+                        errors::Error::WavEncodeError(WavEncodeError::HoundError(e))
+                    })?;
+                }
+            }
+        }
+
+        return Ok(());
+    }
+
+    /// Patches the header with the final length and flushes the output
+    fn finish(&mut self) -> Result<(), errors::Error> {
+        if let Some(writer) = self.writer.take() {
+            writer
+                .finalize()
                 .map_err(|e| errors::Error::WavEncodeError(WavEncodeError::HoundError(e)))?;
         }
 
         return Ok(());
     }
 }
+
+/// Wav encoder for outputs that cannot seek back to patch the header, like
+/// pipes. The header declares an unknown length, which most readers accept.
+pub struct WavStreamEncoder<W: io::Write> {
+    output: W,
+    quantizer: Option<Quantizer>,
+}
+
+impl<W: io::Write> WavStreamEncoder<W> {
+    pub fn new(
+        mut output: W,
+        channels: u16,
+        sample_rate: u32,
+        quantizer: Option<Quantizer>,
+    ) -> Result<Self, WavEncodeError> {
+        let header =
+            get_wav_spec(channels, sample_rate, quantizer.as_ref()).into_header_for_infinite_file();
+
+        output
+            .write_all(&header)
+            .map_err(|e| WavEncodeError::IoError(e))?;
+
+        return Ok(Self { output, quantizer });
+    }
+}
+
+impl<W: io::Write> Encode for WavStreamEncoder<W> {
+    fn encode(&mut self, data: &[f32]) -> Result<(), errors::Error> {
+        let bytes: Vec<u8> = match self.quantizer.as_mut() {
+            Some(quantizer) => {
+                let width = (quantizer.bits() / 8) as usize;
+
+                quantizer
+                    .quantize(data)
+                    .iter()
+                    .flat_map(|sample| sample.to_le_bytes().into_iter().take(width))
+                    .collect()
+            }
+            None => data
+                .iter()
+                .flat_map(|sample| sample.to_le_bytes())
+                .collect(),
+        };
+
+        self.output
+            .write_all(&bytes)
+            .map_err(|e| errors::Error::WavEncodeError(WavEncodeError::IoError(e)))?;
+
+        return Ok(());
+    }
+
+    fn finish(&mut self) -> Result<(), errors::Error> {
+        self.output
+            .flush()
+            .map_err(|e| errors::Error::WavEncodeError(WavEncodeError::IoError(e)))?;
+
+        return Ok(());
+    }
+}
+
+fn get_wav_spec(channels: u16, sample_rate: u32, quantizer: Option<&Quantizer>) -> hound::WavSpec {
+    return match quantizer {
+        Some(quantizer) => hound::WavSpec {
+            sample_rate,
+            channels,
+            bits_per_sample: quantizer.bits(),
+            sample_format: hound::SampleFormat::Int,
+        },
+        None => hound::WavSpec {
+            sample_rate,
+            channels,
+            bits_per_sample: 32,
+            sample_format: hound::SampleFormat::Float,
+        },
+    };
+}
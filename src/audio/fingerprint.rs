@@ -0,0 +1,141 @@
+use std::collections::BTreeMap;
+
+use clap::Args;
+use log::debug;
+use thiserror::Error;
+
+use super::dsp::fingerprint::{self, Fingerprint};
+use super::{registry, utils as audio_utils};
+use crate::internal::utils;
+
+/// Durations of duplicates can differ slightly because of encoder padding
+const MAX_DURATION_DIFFERENCE: f32 = 0.1;
+
+#[derive(Args)]
+pub struct FingerprintCommand {
+    /// Minimum similarity for two files to be considered duplicates. Only used
+    /// when the input is a directory. Note: value will be clamped between 0.0
+    /// and 1.0
+    #[clap(short, long, default_value = "0.85")]
+    threshold: f32,
+
+    /// Whether to also scan subdirectories
+    #[clap(short, long)]
+    recursive: bool,
+}
+
+#[derive(Debug, Error)]
+pub enum FingerprintError {
+    #[error("{0}")]
+    IoError(std::io::Error),
+    #[error("{0}")]
+    DecodeError(audio_utils::DecodeAudioError),
+}
+
+impl FingerprintCommand {
+    pub fn execute(&self, input: &str) -> Result<(), FingerprintError> {
+        if !audio_utils::is_standard_stream(input) && utils::to_absolute_path(input).is_dir() {
+            return self.find_duplicates(input);
+        }
+
+        let fingerprint = get_fingerprint(input)?;
+
+        println!("DURATION={:.2}", fingerprint.duration);
+        println!("FINGERPRINT={}", fingerprint.to_hex());
+
+        return Ok(());
+    }
+
+    fn find_duplicates(&self, input: &str) -> Result<(), FingerprintError> {
+        let directory = utils::to_absolute_path(input);
+        let threshold = self.threshold.clamp(0f32, 1f32);
+
+        let files = utils::list_files(&directory, self.recursive)
+            .map_err(|e| FingerprintError::IoError(e))?;
+
+        let mut fingerprints = Vec::new();
+
+        for file in files.iter().filter(|f| registry::is_decodable(f)) {
+            match get_fingerprint(&file.to_string_lossy()) {
+                Ok(fingerprint) => fingerprints.push((file, fingerprint)),
+                Err(e) => println!("Skipping {}: {}", file.display(), e),
+            }
+        }
+
+        // Sorting by duration means only neighbours need to be compared
+        fingerprints.sort_by(|(_, a), (_, b)| a.duration.total_cmp(&b.duration));
+
+        let mut groups: Vec<usize> = (0..fingerprints.len()).collect();
+
+        for i in 0..fingerprints.len() {
+            let (_, fingerprint) = &fingerprints[i];
+            let max_duration = fingerprint.duration * (1f32 + MAX_DURATION_DIFFERENCE);
+
+            for (j, (_, other)) in fingerprints.iter().enumerate().skip(i + 1) {
+                if other.duration > max_duration {
+                    break;
+                }
+
+                let similarity = fingerprint.similarity(other);
+
+                debug!(
+                    "Similarity between {} and {}: {}",
+                    fingerprints[i].0.display(),
+                    fingerprints[j].0.display(),
+                    similarity
+                );
+
+                if similarity >= threshold {
+                    let root_i = find_group(&mut groups, i);
+                    let root_j = find_group(&mut groups, j);
+                    groups[root_j] = root_i;
+                }
+            }
+        }
+
+        let mut members: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+
+        for i in 0..fingerprints.len() {
+            let root = find_group(&mut groups, i);
+            members.entry(root).or_default().push(i);
+        }
+
+        let mut duplicates: Vec<Vec<usize>> = members.into_values().collect();
+        duplicates.retain(|group| group.len() > 1);
+
+        for (index, group) in duplicates.iter().enumerate() {
+            println!("Duplicate group {}:", index + 1);
+
+            for file in group {
+                println!("  {}", fingerprints[*file].0.display());
+            }
+        }
+
+        println!(
+            "Found {} duplicate groups in {} files",
+            duplicates.len(),
+            fingerprints.len()
+        );
+
+        return Ok(());
+    }
+}
+
+fn get_fingerprint(input: &str) -> Result<Fingerprint, FingerprintError> {
+    let samples = audio_utils::decode_mono(input, fingerprint::SAMPLE_RATE)
+        .map_err(|e| FingerprintError::DecodeError(e))?;
+
+    return Ok(Fingerprint::from_samples(&samples));
+}
+
+fn find_group(groups: &mut [usize], index: usize) -> usize {
+    let mut root = index;
+
+    while groups[root] != root {
+        root = groups[root];
+    }
+
+    groups[index] = root;
+
+    return root;
+}
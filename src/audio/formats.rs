@@ -0,0 +1,22 @@
+use clap::Args;
+
+use super::registry;
+
+#[derive(Args)]
+pub struct AudioFormatsCommand {}
+
+impl AudioFormatsCommand {
+    pub fn execute(&self) {
+        println!("{:<8}{:<16}{:<8}Encode", "Format", "Extensions", "Decode");
+
+        for entry in registry::FORMATS.iter() {
+            println!(
+                "{:<8}{:<16}{:<8}{}",
+                entry.name,
+                entry.extensions.join(", "),
+                if entry.decodable { "yes" } else { "no" },
+                if entry.encoder.is_some() { "yes" } else { "no" }
+            );
+        }
+    }
+}
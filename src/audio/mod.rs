@@ -1,7 +1,14 @@
 pub mod cli;
+mod dsp;
 mod encoders;
+mod registry;
 mod utils;
 
 pub mod boost;
 pub mod convert;
+pub mod convolve;
+pub mod fingerprint;
+pub mod formats;
+pub mod pitch;
 pub mod speed;
+pub mod tempo;
@@ -0,0 +1,84 @@
+use clap::Args;
+use thiserror::Error;
+
+use super::dsp::pitch::PitchShifter;
+use super::{encoders, utils as audio_utils};
+use crate::internal::utils;
+
+/// Largest shift accepted in either direction, in semitones
+const MAX_SEMITONES: f32 = 24.0;
+
+#[derive(Args)]
+pub struct PitchCommand {
+    /// Number of semitones to shift by (12 = one octave up) (-12 = one
+    /// octave down)
+    #[clap(short, long, default_value = "0", allow_hyphen_values = true)]
+    semitones: f32,
+
+    /// Additional shift in cents (100 cents = 1 semitone)
+    #[clap(short, long, default_value = "0", allow_hyphen_values = true)]
+    cents: f32,
+
+    /// Output file. Use - to write to stdout
+    #[clap(short, long)]
+    output: String,
+
+    #[clap(flatten)]
+    encoder_options: encoders::core::EncoderOptions,
+}
+
+#[derive(Debug, Error)]
+pub enum PitchError {
+    #[error("{0}")]
+    DecodeError(audio_utils::DecodeAudioError),
+    #[error("{0}")]
+    EncodeError(encoders::errors::Error),
+    #[error("Pitch shift must be between -24 and 24 semitones")]
+    InvalidShiftError,
+}
+
+impl PitchCommand {
+    pub fn execute(&self, input: &str) -> Result<(), PitchError> {
+        let shift = self.semitones + self.cents / 100.0;
+
+        if !shift.is_finite() || shift.abs() > MAX_SEMITONES {
+            return Err(PitchError::InvalidShiftError);
+        }
+
+        let mut stream =
+            audio_utils::AudioStream::open(input).map_err(|e| PitchError::DecodeError(e))?;
+
+        let mut writer = encoders::core::get_encoder(
+            &self.output,
+            &self.encoder_options,
+            stream.channels as u16,
+            stream.sample_rate,
+        )
+        .map_err(|e| PitchError::EncodeError(e))?;
+
+        let factor = 2f32.powf(shift / 12.0);
+        let mut shifter = PitchShifter::new(factor, stream.sample_rate, stream.channels);
+
+        while let Some(samples) = stream
+            .next_samples()
+            .map_err(|e| PitchError::DecodeError(e))?
+        {
+            writer
+                .encode(&shifter.process(samples))
+                .map_err(|e| PitchError::EncodeError(e))?;
+        }
+
+        writer
+            .encode(&shifter.flush())
+            .map_err(|e| PitchError::EncodeError(e))?;
+
+        writer.finish().map_err(|e| PitchError::EncodeError(e))?;
+
+        if !audio_utils::is_standard_stream(&self.output) {
+            let output_path = utils::to_absolute_path(&self.output);
+            println!("Audio saved to {}", output_path.display());
+        }
+
+        return Ok(());
+    }
+}
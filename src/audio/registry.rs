@@ -0,0 +1,92 @@
+use std::path;
+
+use clap::ValueEnum;
+
+/// Formats that can be written by one of the audio encoders
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum AudioFormat {
+    Wav,
+    Mp3,
+    Ogg,
+    Opus,
+}
+
+pub struct FormatEntry {
+    pub name: &'static str,
+    pub extensions: &'static [&'static str],
+    pub encoder: Option<AudioFormat>,
+    pub decodable: bool,
+}
+
+/// Every known audio format, the extensions it is recognized by and whether
+/// it can be decoded and/or encoded
+pub const FORMATS: &[FormatEntry] = &[
+    FormatEntry {
+        name: "wav",
+        extensions: &["wav", "wave"],
+        encoder: Some(AudioFormat::Wav),
+        decodable: true,
+    },
+    FormatEntry {
+        name: "mp3",
+        extensions: &["mp3"],
+        encoder: Some(AudioFormat::Mp3),
+        decodable: true,
+    },
+    FormatEntry {
+        name: "ogg",
+        extensions: &["ogg", "oga"],
+        encoder: Some(AudioFormat::Ogg),
+        decodable: true,
+    },
+    FormatEntry {
+        name: "opus",
+        extensions: &["opus"],
+        encoder: if cfg!(feature = "opus") {
+            Some(AudioFormat::Opus)
+        } else {
+            None
+        },
+        decodable: false,
+    },
+    FormatEntry {
+        name: "flac",
+        extensions: &["flac"],
+        encoder: None,
+        decodable: true,
+    },
+    FormatEntry {
+        name: "mka",
+        extensions: &["mka", "webm"],
+        encoder: None,
+        decodable: true,
+    },
+];
+
+impl AudioFormat {
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        let extension = extension.to_lowercase();
+
+        return FORMATS
+            .iter()
+            .find(|entry| entry.extensions.contains(&extension.as_str()))
+            .and_then(|entry| entry.encoder);
+    }
+
+    pub fn from_path(file_path: &path::Path) -> Option<Self> {
+        let extension = file_path.extension()?.to_str()?;
+        return Self::from_extension(extension);
+    }
+}
+
+/// Whether a file looks like audio that can be decoded, based on its extension
+pub fn is_decodable(file_path: &path::Path) -> bool {
+    let extension = match file_path.extension().and_then(|e| e.to_str()) {
+        Some(extension) => extension.to_lowercase(),
+        None => return false,
+    };
+
+    return FORMATS
+        .iter()
+        .any(|entry| entry.decodable && entry.extensions.contains(&extension.as_str()));
+}
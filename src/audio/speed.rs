@@ -1,5 +1,5 @@
 use clap::Args;
-use log::{warn};
+use log::warn;
 use symphonia::core::{audio, codecs, errors};
 use symphonia::default;
 use thiserror::Error;
@@ -14,9 +14,12 @@ pub struct AudioSpeedCommand {
     #[clap(short, long)]
     factor: f32,
 
-    /// Output file
+    /// Output file. Use - to write to stdout
     #[clap(short, long)]
     output: String,
+
+    #[clap(flatten)]
+    encoder_options: encoders::core::EncoderOptions,
 }
 
 #[derive(Debug, Error)]
@@ -31,15 +34,18 @@ pub enum AudioSpeedError {
     DecodeError(errors::Error),
     #[error("{0}")]
     EncodeError(encoders::errors::Error),
+    #[error("No supported audio track found")]
+    NoTrackError,
+    #[error("Unknown channel layout")]
+    UnknownChannelLayoutError,
+    #[error("Invalid sample rate")]
+    InvalidSampleRateError,
 }
 
 impl AudioSpeedCommand {
     pub fn execute(&self, input: &str) -> Result<(), AudioSpeedError> {
-        let input_path = utils::to_absolute_path(&input);
-        let output_path = utils::to_absolute_path(&self.output);
-
-        let mut format = audio_utils::get_audio_format(&input_path)
-            .map_err(|e| AudioSpeedError::ProbeError(e))?;
+        let mut format =
+            audio_utils::get_audio_format(input).map_err(|e| AudioSpeedError::ProbeError(e))?;
 
         // Default track or find the first non-null track
         let track = format
@@ -50,21 +56,37 @@ impl AudioSpeedCommand {
                     .iter()
                     .find(|t| t.codec_params.codec != codecs::CODEC_TYPE_NULL)
             })
-            .unwrap();
+            .ok_or(AudioSpeedError::NoTrackError)?;
 
         let track_id = track.id;
 
-        let channels = track.codec_params.channels.unwrap().count();
-        let sample_rate = track.codec_params.sample_rate.unwrap();
+        let channels = track
+            .codec_params
+            .channels
+            .ok_or(AudioSpeedError::UnknownChannelLayoutError)?
+            .count();
+        let sample_rate = track
+            .codec_params
+            .sample_rate
+            .ok_or(AudioSpeedError::InvalidSampleRateError)?;
         let mut decoder = default::get_codecs()
             .make(&track.codec_params, &Default::default())
             .map_err(|e| AudioSpeedError::SymphoniaError(e))?;
 
         let mut sample_buffer: Option<audio::SampleBuffer<f32>> = None;
         let new_sample_rate = (sample_rate as f32 * self.factor.clamp(0f32, f32::MAX)) as u32;
-        let mut writer =
-            encoders::core::get_encoder(&output_path, channels as u16, new_sample_rate)
-                .map_err(|e| AudioSpeedError::EncodeError(e))?;
+
+        if new_sample_rate == 0 {
+            return Err(AudioSpeedError::InvalidSampleRateError);
+        }
+
+        let mut writer = encoders::core::get_encoder(
+            &self.output,
+            &self.encoder_options,
+            channels as u16,
+            new_sample_rate,
+        )
+        .map_err(|e| AudioSpeedError::EncodeError(e))?;
 
         loop {
             let packet = match format.next_packet() {
@@ -114,7 +136,14 @@ impl AudioSpeedCommand {
             }
         }
 
-        println!("Audio saved to {}", output_path.display());
+        writer
+            .finish()
+            .map_err(|e| AudioSpeedError::EncodeError(e))?;
+
+        if !audio_utils::is_standard_stream(&self.output) {
+            let output_path = utils::to_absolute_path(&self.output);
+            println!("Audio saved to {}", output_path.display());
+        }
 
         Ok(())
     }
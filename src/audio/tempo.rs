@@ -0,0 +1,116 @@
+use std::fs;
+
+use clap::{Args, ValueEnum};
+use serde_json;
+use thiserror::Error;
+
+use super::dsp::tempo;
+use super::utils as audio_utils;
+use crate::internal::utils;
+
+#[derive(Args)]
+pub struct TempoCommand {
+    /// Slowest tempo to consider, in beats per minute
+    #[clap(long, default_value = "60")]
+    min_bpm: f32,
+
+    /// Fastest tempo to consider, in beats per minute
+    #[clap(long, default_value = "200")]
+    max_bpm: f32,
+
+    /// File to export the beat timestamps to
+    #[clap(short, long)]
+    beats: Option<String>,
+
+    /// Format of the exported beats. Inferred from the extension of the beats
+    /// file when not set
+    #[clap(long)]
+    beats_format: Option<BeatsFormat>,
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+pub enum BeatsFormat {
+    Json,
+    Csv,
+}
+
+#[derive(Debug, Error)]
+pub enum TempoError {
+    #[error("{0}")]
+    IoError(std::io::Error),
+    #[error("{0}")]
+    DecodeError(audio_utils::DecodeAudioError),
+    #[error("{0}")]
+    JsonError(serde_json::Error),
+    #[error("Invalid tempo range")]
+    InvalidRangeError,
+    #[error("Audio is too short to estimate its tempo")]
+    TooShortError,
+    #[error("Could not infer the beats format, use --beats-format to set it")]
+    UnknownBeatsFormatError,
+}
+
+impl TempoCommand {
+    pub fn execute(&self, input: &str) -> Result<(), TempoError> {
+        if self.min_bpm <= 0f32 || self.min_bpm >= self.max_bpm {
+            return Err(TempoError::InvalidRangeError);
+        }
+
+        let samples = audio_utils::decode_mono(input, tempo::SAMPLE_RATE)
+            .map_err(|e| TempoError::DecodeError(e))?;
+
+        let estimate = tempo::estimate_tempo(&samples, self.min_bpm, self.max_bpm)
+            .ok_or(TempoError::TooShortError)?;
+
+        println!("Tempo: {:.2} BPM", estimate.bpm);
+
+        if let Some(beats) = &self.beats {
+            let beats_path = utils::to_absolute_path(beats);
+
+            let format = match self.beats_format {
+                Some(format) => format,
+                None => match beats_path
+                    .extension()
+                    .and_then(|e| e.to_str())
+                    .map(|e| e.to_lowercase())
+                    .as_deref()
+                {
+                    Some("json") => BeatsFormat::Json,
+                    Some("csv") => BeatsFormat::Csv,
+                    _ => return Err(TempoError::UnknownBeatsFormatError),
+                },
+            };
+
+            let contents = match format {
+                BeatsFormat::Json => serde_json::to_string_pretty(&serde_json::json!({
+                    "bpm": round_to_thousandths(estimate.bpm),
+                    "beats": estimate
+                        .beats
+                        .iter()
+                        .map(|time| round_to_thousandths(*time))
+                        .collect::<Vec<f64>>(),
+                }))
+                .map_err(|e| TempoError::JsonError(e))?,
+                BeatsFormat::Csv => {
+                    let mut csv = String::from("beat,time\n");
+
+                    for (index, time) in estimate.beats.iter().enumerate() {
+                        csv.push_str(&format!("{},{:.3}\n", index + 1, time));
+                    }
+
+                    csv
+                }
+            };
+
+            fs::write(&beats_path, contents).map_err(|e| TempoError::IoError(e))?;
+
+            println!("Beats saved to {}", beats_path.display());
+        }
+
+        return Ok(());
+    }
+}
+
+fn round_to_thousandths(value: f32) -> f64 {
+    return (value as f64 * 1000.0).round() / 1000.0;
+}
@@ -1,9 +1,16 @@
-use std::{fs, path};
+use std::fs;
 
-use symphonia::core::{formats, io, probe};
+use log::warn;
+use symphonia::core::{audio, codecs, errors, formats, io, probe};
 use symphonia::default;
 use thiserror::Error;
 
+use super::dsp::resample::Resampler;
+use crate::internal::utils;
+
+/// Path used to read from stdin or write to stdout
+pub const STANDARD_STREAM: &str = "-";
+
 #[derive(Debug, Error)]
 pub enum ProbeAudioError {
     #[error("{0}")]
@@ -12,11 +19,161 @@ pub enum ProbeAudioError {
     SymphoniaError(symphonia::core::errors::Error),
 }
 
-pub fn probe_audio(audio_file: &path::Path) -> Result<probe::ProbeResult, ProbeAudioError> {
+#[derive(Debug, Error)]
+pub enum DecodeAudioError {
+    #[error("{0}")]
+    IoError(std::io::Error),
+    #[error("{0}")]
+    SymphoniaError(errors::Error),
+    #[error("{0}")]
+    ProbeError(ProbeAudioError),
+    #[error("{0}")]
+    DecodeError(errors::Error),
+    #[error("No supported audio track found")]
+    NoTrackError,
+    #[error("Unknown channel layout")]
+    UnknownChannelLayoutError,
+    #[error("Invalid sample rate")]
+    InvalidSampleRateError,
+}
+
+/// Decodes the default track of an input into interleaved f32 samples
+pub struct AudioStream {
+    format: Box<dyn formats::FormatReader>,
+    decoder: Box<dyn codecs::Decoder>,
+    track_id: u32,
+    sample_buffer: Option<audio::SampleBuffer<f32>>,
+    pub channels: usize,
+    pub sample_rate: u32,
+}
+
+impl AudioStream {
+    pub fn open(input: &str) -> Result<Self, DecodeAudioError> {
+        let format = get_audio_format(input).map_err(|e| DecodeAudioError::ProbeError(e))?;
+
+        // Default track or find the first non-null track
+        let track = format
+            .default_track()
+            .or_else(|| {
+                format
+                    .tracks()
+                    .iter()
+                    .find(|t| t.codec_params.codec != codecs::CODEC_TYPE_NULL)
+            })
+            .ok_or(DecodeAudioError::NoTrackError)?;
+
+        let track_id = track.id;
+        let channels = track
+            .codec_params
+            .channels
+            .ok_or(DecodeAudioError::UnknownChannelLayoutError)?
+            .count();
+        let sample_rate = track
+            .codec_params
+            .sample_rate
+            .ok_or(DecodeAudioError::InvalidSampleRateError)?;
+
+        let decoder = default::get_codecs()
+            .make(&track.codec_params, &Default::default())
+            .map_err(|e| DecodeAudioError::SymphoniaError(e))?;
+
+        return Ok(Self {
+            format,
+            decoder,
+            track_id,
+            sample_buffer: None,
+            channels,
+            sample_rate,
+        });
+    }
+
+    /// Returns the next block of interleaved samples, or `None` once the
+    /// stream is exhausted
+    pub fn next_samples(&mut self) -> Result<Option<&[f32]>, DecodeAudioError> {
+        loop {
+            let packet = match self.format.next_packet() {
+                Ok(packet) => packet,
+                Err(errors::Error::IoError(err)) => {
+                    if err.kind() == std::io::ErrorKind::UnexpectedEof {
+                        return Ok(None);
+                    } else {
+                        return Err(DecodeAudioError::IoError(err));
+                    }
+                }
+                Err(e) => return Err(DecodeAudioError::DecodeError(e)),
+            };
+
+            if packet.track_id() != self.track_id {
+                continue;
+            }
+
+            let decoded_packet = match self.decoder.decode(&packet) {
+                Ok(decoded_packet) => decoded_packet,
+                Err(errors::Error::DecodeError(err)) => {
+                    warn!("Decode error: {}", err);
+                    continue;
+                }
+                Err(e) => return Err(DecodeAudioError::SymphoniaError(e)),
+            };
+
+            let required_capacity =
+                decoded_packet.capacity() * decoded_packet.spec().channels.count();
+
+            let has_capacity = match &self.sample_buffer {
+                Some(buffer) => buffer.capacity() >= required_capacity,
+                None => false,
+            };
+
+            if !has_capacity {
+                self.sample_buffer = Some(audio::SampleBuffer::new(
+                    decoded_packet.capacity() as u64,
+                    *decoded_packet.spec(),
+                ));
+            }
+
+            return match self.sample_buffer.as_mut() {
+                Some(buffer) => {
+                    buffer.copy_interleaved_ref(decoded_packet);
+                    Ok(Some(buffer.samples()))
+                }
+                None => Ok(None),
+            };
+        }
+    }
+}
+
+/// Decodes a whole input into mono samples at the given sample rate, as
+/// expected by the analysis commands
+pub fn decode_mono(input: &str, sample_rate: u32) -> Result<Vec<f32>, DecodeAudioError> {
+    let mut stream = AudioStream::open(input)?;
+
+    let channels = stream.channels;
+    let mut resampler = Resampler::new(stream.sample_rate, sample_rate, 1);
+    let mut samples = Vec::new();
+
+    while let Some(block) = stream.next_samples()? {
+        samples.extend(resampler.process(&downmix_to_mono(block, channels)));
+    }
+
+    samples.extend(resampler.flush());
+
+    return Ok(samples);
+}
+
+pub fn is_standard_stream(path: &str) -> bool {
+    return path == STANDARD_STREAM;
+}
+
+pub fn probe_audio(input: &str) -> Result<probe::ProbeResult, ProbeAudioError> {
     let mut hint = probe::Hint::new();
-    let format_opts = formats::FormatOptions::default();
-    let input_file = fs::File::open(audio_file).map_err(|e| ProbeAudioError::IoError(e))?;
-    let media_source = io::MediaSourceStream::new(Box::new(input_file), Default::default());
+
+    if is_standard_stream(input) {
+        let stdin = io::ReadOnlySource::new(std::io::stdin());
+        return probe_media_source(Box::new(stdin), &hint);
+    }
+
+    let audio_file = utils::to_absolute_path(input);
+    let input_file = fs::File::open(&audio_file).map_err(|e| ProbeAudioError::IoError(e))?;
 
     if let Some(extension) = audio_file.extension() {
         if let Some(extension_str) = extension.to_str() {
@@ -24,17 +181,25 @@ pub fn probe_audio(audio_file: &path::Path) -> Result<probe::ProbeResult, ProbeA
         }
     }
 
+    return probe_media_source(Box::new(input_file), &hint);
+}
+
+pub fn probe_media_source(
+    source: Box<dyn io::MediaSource>,
+    hint: &probe::Hint,
+) -> Result<probe::ProbeResult, ProbeAudioError> {
+    let format_opts = formats::FormatOptions::default();
+    let media_source = io::MediaSourceStream::new(source, Default::default());
+
     let probe = default::get_probe()
-        .format(&hint, media_source, &format_opts, &Default::default())
+        .format(hint, media_source, &format_opts, &Default::default())
         .map_err(|e| ProbeAudioError::SymphoniaError(e))?;
 
     return Ok(probe);
 }
 
-pub fn get_audio_format(
-    audio_file: &path::Path,
-) -> Result<Box<dyn formats::FormatReader>, ProbeAudioError> {
-    let probe = probe_audio(audio_file)?;
+pub fn get_audio_format(input: &str) -> Result<Box<dyn formats::FormatReader>, ProbeAudioError> {
+    let probe = probe_audio(input)?;
     return Ok(probe.format);
 }
 
@@ -47,3 +212,10 @@ pub fn interleaved_to_planar(interleaved: &[f32], channels: usize) -> Vec<Vec<f3
 
     return planar;
 }
+
+pub fn downmix_to_mono(interleaved: &[f32], channels: usize) -> Vec<f32> {
+    return interleaved
+        .chunks(channels)
+        .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+        .collect();
+}
@@ -6,6 +6,7 @@ use thiserror::Error;
 
 use crate::audio::cli::{AudioCommand, AudioError};
 use crate::image::cli::{ImageCommand, ImageError};
+use crate::job::run::{RunCommand, RunError};
 use crate::video::cli::{VideoCommand, VideoError};
 
 #[derive(Parser)]
@@ -32,6 +33,10 @@ pub enum EMode {
     /// Audio operations
     #[clap(subcommand, name = "audio")]
     Audio(AudioCommand),
+
+    /// Run the steps described in a job file (toml, yaml or json)
+    #[clap(name = "run")]
+    Run(RunCommand),
 }
 
 impl fmt::Display for EMode {
@@ -40,6 +45,7 @@ impl fmt::Display for EMode {
             EMode::Image(_) => write!(f, "image"),
             EMode::Video(_) => write!(f, "video"),
             EMode::Audio(_) => write!(f, "audio"),
+            EMode::Run(_) => write!(f, "run"),
         }
     }
 }
@@ -52,6 +58,8 @@ pub enum CliError {
     VideoError(VideoError),
     #[error("{0}")]
     AudioError(AudioError),
+    #[error("{0}")]
+    RunError(RunError),
 }
 
 impl Cli {
@@ -70,6 +78,10 @@ impl Cli {
             EMode::Audio(audio) => audio
                 .execute(self.input.as_deref())
                 .map_err(|e| CliError::AudioError(e)),
+
+            EMode::Run(run) => run
+                .execute(self.input.as_deref())
+                .map_err(|e| CliError::RunError(e)),
         }
     }
 }
@@ -0,0 +1,329 @@
+use std::path;
+
+use clap::Args;
+use image::{DynamicImage, Rgba32FImage};
+use thiserror::Error;
+
+use super::batch::{self, BatchOperation};
+use super::utils as image_utils;
+use super::{metadata, output};
+use crate::internal::utils;
+
+/// Share of the darkest and of the brightest pixels auto levels lets clip
+const AUTO_LEVELS_CLIP: f32 = 0.005;
+
+#[derive(Args)]
+pub struct AdjustCommand {
+    /// Exposure change in stops. Each stop doubles or halves the light
+    #[clap(long, default_value = "0", allow_negative_numbers = true)]
+    exposure: f32,
+
+    /// Contrast between -100 and 100. Positive values pull tones away from
+    /// middle gray, negative values push them towards it
+    #[clap(long, default_value = "0", allow_negative_numbers = true)]
+    contrast: f32,
+
+    /// Saturation between -100 and 100. -100 removes all color
+    #[clap(long, default_value = "0", allow_negative_numbers = true)]
+    saturation: f32,
+
+    /// Hue rotation in degrees
+    #[clap(long, default_value = "0", allow_negative_numbers = true)]
+    hue: f32,
+
+    /// Gamma correction. Values above 1 brighten the midtones, values below
+    /// 1 darken them
+    #[clap(long, default_value = "1.0")]
+    gamma: f32,
+
+    /// Stretch the tones so the darkest part becomes black and the brightest
+    /// part white
+    #[clap(long)]
+    auto_levels: bool,
+
+    /// Tone curve through input:output control points between 0 and 255,
+    /// such as "0:0,64:48,192:210,255:255"
+    #[clap(long)]
+    curve: Option<Curve>,
+
+    /// Output path. A directory when the input is a directory or a glob
+    #[clap(short, long)]
+    output: String,
+
+    #[clap(flatten)]
+    output_options: output::OutputOptions,
+
+    #[clap(flatten)]
+    batch: batch::BatchOptions,
+}
+
+#[derive(Debug, Error)]
+pub enum AdjustError {
+    #[error("Image crate error")]
+    ImageCrateError(image::ImageError),
+    #[error("{0}")]
+    BatchError(batch::BatchError),
+}
+
+/// Smooth tone curve through control points, using monotone cubic
+/// interpolation so it never overshoots between points
+#[derive(Clone, Debug)]
+pub struct Curve {
+    points: Vec<(f32, f32)>,
+    tangents: Vec<f32>,
+}
+
+impl Curve {
+    fn new(points: Vec<(f32, f32)>) -> Self {
+        let count = points.len();
+
+        let secants: Vec<f32> = points
+            .windows(2)
+            .map(|pair| (pair[1].1 - pair[0].1) / (pair[1].0 - pair[0].0))
+            .collect();
+
+        let mut tangents = vec![0.0; count];
+        tangents[0] = secants[0];
+        tangents[count - 1] = secants[count - 2];
+
+        for index in 1..count - 1 {
+            if secants[index - 1] * secants[index] > 0.0 {
+                tangents[index] = (secants[index - 1] + secants[index]) / 2.0;
+            }
+        }
+
+        // Fritsch-Carlson limits keep each segment monotone
+        for (index, secant) in secants.iter().enumerate() {
+            if *secant == 0.0 {
+                tangents[index] = 0.0;
+                tangents[index + 1] = 0.0;
+                continue;
+            }
+
+            let a = tangents[index] / secant;
+            let b = tangents[index + 1] / secant;
+            let length = (a * a + b * b).sqrt();
+
+            if length > 3.0 {
+                tangents[index] = 3.0 / length * a * secant;
+                tangents[index + 1] = 3.0 / length * b * secant;
+            }
+        }
+
+        return Self { points, tangents };
+    }
+
+    /// Output of the curve for an input between 0 and 1. Inputs outside of
+    /// the control points keep the value of the closest point.
+    fn evaluate(&self, value: f32) -> f32 {
+        let first = self.points[0];
+        let last = self.points[self.points.len() - 1];
+
+        if value <= first.0 {
+            return first.1;
+        }
+
+        if value >= last.0 {
+            return last.1;
+        }
+
+        let index = self.points.partition_point(|point| point.0 <= value) - 1;
+        let (x0, y0) = self.points[index];
+        let (x1, y1) = self.points[index + 1];
+
+        let width = x1 - x0;
+        let t = (value - x0) / width;
+        let t2 = t * t;
+        let t3 = t2 * t;
+
+        return (2.0 * t3 - 3.0 * t2 + 1.0) * y0
+            + (t3 - 2.0 * t2 + t) * width * self.tangents[index]
+            + (-2.0 * t3 + 3.0 * t2) * y1
+            + (t3 - t2) * width * self.tangents[index + 1];
+    }
+}
+
+impl std::str::FromStr for Curve {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut points = Vec::new();
+
+        for point in value.split(',') {
+            let parsed = point.split_once(':').and_then(|(x, y)| {
+                Some((x.trim().parse::<f32>().ok()?, y.trim().parse::<f32>().ok()?))
+            });
+
+            match parsed {
+                Some((x, y)) if (0.0..=255.0).contains(&x) && (0.0..=255.0).contains(&y) => {
+                    points.push((x / 255.0, y / 255.0));
+                }
+                _ => {
+                    return Err(format!(
+                        "Expected input:output between 0 and 255: {}",
+                        point
+                    ))
+                }
+            }
+        }
+
+        points.sort_by(|a, b| a.0.total_cmp(&b.0));
+        points.dedup_by(|a, b| a.0 == b.0);
+
+        if points.len() < 2 {
+            return Err("A curve needs at least two points with different inputs".to_string());
+        }
+
+        return Ok(Curve::new(points));
+    }
+}
+
+impl AdjustCommand {
+    pub fn execute(&self, input: &str) -> Result<(), AdjustError> {
+        if batch::is_batch_input(input) {
+            return batch::execute(self, input, &self.output, &self.batch)
+                .map_err(|e| AdjustError::BatchError(e));
+        }
+
+        let input_path = utils::to_absolute_path(input);
+        let output_path = utils::to_absolute_path(&self.output);
+
+        self.process(&input_path, &output_path)?;
+
+        println!("Image saved to {}", output_path.display());
+
+        return Ok(());
+    }
+
+    /// Applies the adjustments in order: levels, then exposure, saturation
+    /// and hue in linear light, then contrast, gamma and the curve on the
+    /// encoded tones
+    fn adjust(&self, img: &DynamicImage) -> DynamicImage {
+        let mut pixels = img.to_rgba32f();
+
+        let (black, white) = if self.auto_levels {
+            get_levels(&pixels)
+        } else {
+            (0.0, 1.0)
+        };
+
+        let exposure = 2f32.powf(self.exposure);
+        let contrast = (100.0 + self.contrast.clamp(-100.0, 100.0)) / 100.0;
+        let saturation = (100.0 + self.saturation.clamp(-100.0, 100.0)) / 100.0;
+        let hue = get_hue_rotation(self.hue);
+        let gamma = 1.0 / self.gamma.max(0.01);
+
+        for pixel in pixels.pixels_mut() {
+            let mut color = [0f32; 3];
+
+            for (channel, value) in color.iter_mut().enumerate() {
+                let leveled = ((pixel[channel] - black) / (white - black)).clamp(0.0, 1.0);
+                *value = image_utils::srgb_to_linear(leveled) * exposure;
+            }
+
+            let gray = image_utils::luminance(color[0], color[1], color[2]);
+
+            for value in color.iter_mut() {
+                *value = gray + (*value - gray) * saturation;
+            }
+
+            for (channel, row) in hue.iter().enumerate() {
+                let rotated = row[0] * color[0] + row[1] * color[1] + row[2] * color[2];
+                let encoded = image_utils::linear_to_srgb(rotated.clamp(0.0, 1.0));
+                let contrasted = ((encoded - 0.5) * contrast + 0.5).clamp(0.0, 1.0);
+                let corrected = contrasted.powf(gamma);
+
+                pixel[channel] = match &self.curve {
+                    Some(curve) => curve.evaluate(corrected),
+                    None => corrected,
+                };
+            }
+        }
+
+        return image_utils::to_color_type(DynamicImage::ImageRgba32F(pixels), img.color());
+    }
+}
+
+impl BatchOperation for AdjustCommand {
+    type Error = AdjustError;
+
+    fn process(
+        &self,
+        input_path: &path::Path,
+        output_path: &path::Path,
+    ) -> Result<(), AdjustError> {
+        let (img, metadata) =
+            metadata::open(input_path).map_err(|e| AdjustError::ImageCrateError(e))?;
+
+        let adjusted = self.adjust(&img);
+
+        output::save(&adjusted, output_path, &metadata, &self.output_options)
+            .map_err(|e| AdjustError::ImageCrateError(e))?;
+
+        return Ok(());
+    }
+}
+
+/// Tones of the luminance histogram below and above which a small share of
+/// the pixels falls, to be used as the new black and white
+fn get_levels(pixels: &Rgba32FImage) -> (f32, f32) {
+    let mut histogram = [0usize; 256];
+
+    for pixel in pixels.pixels() {
+        let gray = image_utils::luminance(pixel[0], pixel[1], pixel[2]);
+        histogram[(gray.clamp(0.0, 1.0) * 255.0).round() as usize] += 1;
+    }
+
+    let clipped = (pixels.pixels().len() as f32 * AUTO_LEVELS_CLIP) as usize;
+
+    let mut count = 0;
+    let black = histogram
+        .iter()
+        .position(|bin| {
+            count += bin;
+            count > clipped
+        })
+        .unwrap_or(0);
+
+    count = 0;
+    let white = 255
+        - histogram
+            .iter()
+            .rev()
+            .position(|bin| {
+                count += bin;
+                count > clipped
+            })
+            .unwrap_or(0);
+
+    if white <= black {
+        return (0.0, 1.0);
+    }
+
+    return (black as f32 / 255.0, white as f32 / 255.0);
+}
+
+/// Matrix rotating colors around the gray axis, keeping their luminance.
+/// Same coefficients as the hueRotate filter of SVG and CSS.
+fn get_hue_rotation(degrees: f32) -> [[f32; 3]; 3] {
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    let [red, green, blue] = image_utils::LUMA_WEIGHTS;
+
+    return [
+        [
+            red + cos * (1.0 - red) - sin * red,
+            green - cos * green - sin * green,
+            blue - cos * blue + sin * (1.0 - blue),
+        ],
+        [
+            red - cos * red + sin * 0.143,
+            green + cos * (1.0 - green) + sin * 0.140,
+            blue - cos * blue - sin * 0.283,
+        ],
+        [
+            red - cos * red - sin * (1.0 - red),
+            green - cos * green + sin * green,
+            blue + cos * (1.0 - blue) + sin * blue,
+        ],
+    ];
+}
@@ -0,0 +1,234 @@
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::{fmt, fs, io, path};
+
+use clap::Args;
+use rayon::prelude::*;
+use thiserror::Error;
+
+use crate::internal::utils;
+
+/// Characters that turn an input into a glob pattern
+const GLOB_CHARACTERS: &[char] = &['*', '?', '['];
+
+#[derive(Args, Clone)]
+pub struct BatchOptions {
+    /// Look for images in subdirectories when the input is a directory
+    #[clap(short('R'), long)]
+    pub recursive: bool,
+
+    /// Name of each output file when processing several images, relative to
+    /// the output directory. Supports {stem}, {ext} and {name}
+    #[clap(long, default_value = "{name}")]
+    pub template: String,
+
+    /// Number of images processed at the same time. Defaults to the number
+    /// of CPUs
+    #[clap(short, long)]
+    pub jobs: Option<usize>,
+}
+
+#[derive(Debug, Error)]
+pub enum BatchError {
+    #[error("{0}")]
+    IoError(io::Error),
+    #[error("{0}")]
+    PatternError(glob::PatternError),
+    #[error("{0}")]
+    ThreadPoolError(rayon::ThreadPoolBuildError),
+    #[error("No images found in {0}")]
+    NoImagesError(String),
+    #[error("More than one image would be saved to {0}")]
+    OutputConflictError(String),
+    #[error("{0} would be overwritten, use another output directory or a --template")]
+    OverwriteError(String),
+    #[error("{0} image(s) could not be processed")]
+    FailedImagesError(usize),
+}
+
+/// Image command that can be applied to many files at once
+pub trait BatchOperation: Sync {
+    type Error: fmt::Display;
+
+    fn process(&self, input_path: &path::Path, output_path: &path::Path)
+        -> Result<(), Self::Error>;
+}
+
+/// Whether an input refers to several images, either as a directory or as a
+/// glob pattern
+pub fn is_batch_input(input: &str) -> bool {
+    return is_pattern(input) || utils::to_absolute_path(input).is_dir();
+}
+
+/// Whether an input is a glob pattern. Existing paths such as `photo[1].jpg`
+/// are taken literally even when they contain glob characters.
+pub fn is_pattern(input: &str) -> bool {
+    return input.contains(GLOB_CHARACTERS) && !utils::to_absolute_path(input).exists();
+}
+
+/// Applies an operation to every image matched by the input, writing the
+/// results to the output directory, and prints a summary at the end
+pub fn execute<O: BatchOperation>(
+    operation: &O,
+    input: &str,
+    output: &str,
+    options: &BatchOptions,
+) -> Result<(), BatchError> {
+    let output_directory = utils::to_absolute_path(output);
+    let jobs = get_jobs(input, &output_directory, options)?;
+
+    if jobs.is_empty() {
+        return Err(BatchError::NoImagesError(input.to_string()));
+    }
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(options.jobs.unwrap_or(0))
+        .build()
+        .map_err(|e| BatchError::ThreadPoolError(e))?;
+
+    let failures = AtomicUsize::new(0);
+
+    pool.install(|| {
+        jobs.par_iter().for_each(|(input_path, output_path)| {
+            let result = match output_path.parent() {
+                Some(parent) => fs::create_dir_all(parent).map_err(|e| e.to_string()),
+                None => Ok(()),
+            }
+            .and_then(|_| {
+                operation
+                    .process(input_path, output_path)
+                    .map_err(|e| e.to_string())
+            });
+
+            match result {
+                Ok(_) => println!("Image saved to {}", output_path.display()),
+                Err(e) => {
+                    failures.fetch_add(1, Ordering::Relaxed);
+                    println!("Failed to process {}: {}", input_path.display(), e);
+                }
+            }
+        });
+    });
+
+    let failures = failures.into_inner();
+
+    println!(
+        "Processed {} image(s): {} succeeded, {} failed",
+        jobs.len(),
+        jobs.len() - failures,
+        failures
+    );
+
+    if failures > 0 {
+        return Err(BatchError::FailedImagesError(failures));
+    }
+
+    return Ok(());
+}
+
+/// Pairs every matched image with the path it will be saved to
+fn get_jobs(
+    input: &str,
+    output_directory: &path::Path,
+    options: &BatchOptions,
+) -> Result<Vec<(path::PathBuf, path::PathBuf)>, BatchError> {
+    let (base_directory, inputs) = if is_pattern(input) {
+        let pattern = utils::to_absolute_path(input);
+        let base_directory = get_pattern_base(&pattern);
+
+        let inputs = glob::glob(&pattern.to_string_lossy())
+            .map_err(|e| BatchError::PatternError(e))?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.is_file())
+            .collect::<Vec<_>>();
+
+        (base_directory, inputs)
+    } else {
+        let base_directory = utils::to_absolute_path(input);
+        let inputs = utils::list_files(&base_directory, options.recursive)
+            .map_err(|e| BatchError::IoError(e))?;
+
+        (base_directory, inputs)
+    };
+
+    let mut jobs: Vec<(path::PathBuf, path::PathBuf)> = Vec::new();
+    let mut output_paths = HashSet::new();
+    let input_paths: HashSet<path::PathBuf> = inputs.iter().cloned().collect();
+
+    for input_path in inputs {
+        if image::ImageFormat::from_path(&input_path).is_err() {
+            continue;
+        }
+
+        // Skip the results of a previous run when writing inside the input
+        if output_directory != base_directory && input_path.starts_with(output_directory) {
+            continue;
+        }
+
+        let relative_directory = input_path
+            .parent()
+            .and_then(|parent| parent.strip_prefix(&base_directory).ok())
+            .unwrap_or(path::Path::new(""));
+
+        let output_path = output_directory
+            .join(relative_directory)
+            .join(get_output_name(&input_path, &options.template));
+
+        // Writing to the input directory with the default template would
+        // replace the source images
+        if input_paths.contains(&output_path) {
+            return Err(BatchError::OverwriteError(
+                output_path.display().to_string(),
+            ));
+        }
+
+        if !output_paths.insert(output_path.clone()) {
+            return Err(BatchError::OutputConflictError(
+                output_path.display().to_string(),
+            ));
+        }
+
+        jobs.push((input_path, output_path));
+    }
+
+    return Ok(jobs);
+}
+
+/// Longest leading part of a glob pattern without any glob characters
+fn get_pattern_base(pattern: &path::Path) -> path::PathBuf {
+    let mut base = path::PathBuf::new();
+
+    for component in pattern.components() {
+        if component
+            .as_os_str()
+            .to_string_lossy()
+            .contains(GLOB_CHARACTERS)
+        {
+            break;
+        }
+
+        base.push(component);
+    }
+
+    return base;
+}
+
+fn get_output_name(input_path: &path::Path, template: &str) -> String {
+    let stem = input_path
+        .file_stem()
+        .map(|s| s.to_string_lossy())
+        .unwrap_or_default();
+    let extension = input_path
+        .extension()
+        .map(|e| e.to_string_lossy())
+        .unwrap_or_default();
+    let name = input_path
+        .file_name()
+        .map(|n| n.to_string_lossy())
+        .unwrap_or_default();
+
+    return template
+        .replace("{stem}", &stem)
+        .replace("{ext}", &extension)
+        .replace("{name}", &name);
+}
@@ -1,7 +1,11 @@
+use std::path;
+
 use clap::Args;
 
 use thiserror::Error;
 
+use super::batch::{self, BatchOperation};
+use super::{metadata, output};
 use crate::internal::utils;
 
 #[derive(Args)]
@@ -11,33 +15,60 @@ pub struct BlurCommand {
     #[clap(short, long)]
     intensity: f32,
 
-    /// Output path
+    /// Output path. A directory when the input is a directory or a glob
     #[clap(short, long)]
     output: String,
+
+    #[clap(flatten)]
+    output_options: output::OutputOptions,
+
+    #[clap(flatten)]
+    batch: batch::BatchOptions,
 }
 
 #[derive(Debug, Error)]
 pub enum BlurError {
     #[error("Image crate error")]
     ImageCrateError(image::ImageError),
+    #[error("{0}")]
+    BatchError(batch::BatchError),
 }
 
 impl BlurCommand {
     pub fn execute(&self, input: &str) -> Result<(), BlurError> {
+        if batch::is_batch_input(input) {
+            return batch::execute(self, input, &self.output, &self.batch)
+                .map_err(|e| BlurError::BatchError(e));
+        }
+
         let input_path = utils::to_absolute_path(input);
         let output_path = utils::to_absolute_path(&self.output);
-        let intensity = self.intensity.clamp(1f32, 100f32);
 
-        let img = image::open(input_path).map_err(|e| BlurError::ImageCrateError(e))?;
+        self.process(&input_path, &output_path)?;
 
-        let blurred = img.blur(intensity);
+        println!("Image saved to {}", output_path.display());
 
-        blurred
-            .save(&output_path)
-            .map_err(|e| BlurError::ImageCrateError(e))?;
+        return Ok(());
+    }
+}
 
-        println!("Image saved to {}", output_path.display());
+impl BatchOperation for BlurCommand {
+    type Error = BlurError;
+
+    fn process(&self, input_path: &path::Path, output_path: &path::Path) -> Result<(), BlurError> {
+        let (img, metadata) =
+            metadata::open(input_path).map_err(|e| BlurError::ImageCrateError(e))?;
+
+        let blurred = blur(&img, self.intensity);
+
+        output::save(&blurred, output_path, &metadata, &self.output_options)
+            .map_err(|e| BlurError::ImageCrateError(e))?;
 
         return Ok(());
     }
 }
+
+/// Applies a gaussian blur, with the intensity clamped between 1 and 100
+pub fn blur(img: &image::DynamicImage, intensity: f32) -> image::DynamicImage {
+    return img.blur(intensity.clamp(1f32, 100f32));
+}
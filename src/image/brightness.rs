@@ -1,7 +1,11 @@
+use std::path;
+
 use clap::Args;
 
 use thiserror::Error;
 
+use super::batch::{self, BatchOperation};
+use super::{metadata, output};
 use crate::internal::utils;
 
 #[derive(Args)]
@@ -12,33 +16,65 @@ pub struct BrightnessCommand {
     #[clap(short, long)]
     intensity: i32,
 
-    /// Output path
+    /// Output path. A directory when the input is a directory or a glob
     #[clap(short, long)]
     output: String,
+
+    #[clap(flatten)]
+    output_options: output::OutputOptions,
+
+    #[clap(flatten)]
+    batch: batch::BatchOptions,
 }
 
 #[derive(Debug, Error)]
 pub enum BrightnessError {
     #[error("Image crate error")]
     ImageCrateError(image::ImageError),
+    #[error("{0}")]
+    BatchError(batch::BatchError),
 }
 
 impl BrightnessCommand {
     pub fn execute(&self, input: &str) -> Result<(), BrightnessError> {
+        if batch::is_batch_input(input) {
+            return batch::execute(self, input, &self.output, &self.batch)
+                .map_err(|e| BrightnessError::BatchError(e));
+        }
+
         let input_path = utils::to_absolute_path(input);
         let output_path = utils::to_absolute_path(&self.output);
-        let intensity = self.intensity.clamp(-100i32, 100i32);
 
-        let img = image::open(input_path).map_err(|e| BrightnessError::ImageCrateError(e))?;
+        self.process(&input_path, &output_path)?;
 
-        let brighten_img = img.brighten(intensity);
+        println!("Image saved to {}", output_path.display());
 
-        brighten_img
-            .save(&output_path)
-            .map_err(|e| BrightnessError::ImageCrateError(e))?;
+        return Ok(());
+    }
+}
 
-        println!("Image saved to {}", output_path.display());
+impl BatchOperation for BrightnessCommand {
+    type Error = BrightnessError;
+
+    fn process(
+        &self,
+        input_path: &path::Path,
+        output_path: &path::Path,
+    ) -> Result<(), BrightnessError> {
+        let (img, metadata) =
+            metadata::open(input_path).map_err(|e| BrightnessError::ImageCrateError(e))?;
+
+        let brighten_img = brighten(&img, self.intensity);
+
+        output::save(&brighten_img, output_path, &metadata, &self.output_options)
+            .map_err(|e| BrightnessError::ImageCrateError(e))?;
 
         return Ok(());
     }
 }
+
+/// Brightens or darkens an image, with the intensity clamped between -100
+/// and 100
+pub fn brighten(img: &image::DynamicImage, intensity: i32) -> image::DynamicImage {
+    return img.brighten(intensity.clamp(-100i32, 100i32));
+}
@@ -2,12 +2,20 @@ use clap::Subcommand;
 use log::info;
 use thiserror::Error;
 
+use super::adjust::{AdjustCommand, AdjustError};
 use super::blur::{BlurCommand, BlurError};
 use super::brightness::{BrightnessCommand, BrightnessError};
 use super::convert::{ConvertCommand, ConvertError};
+use super::crop::{CropCommand, CropError};
+use super::filter::{FilterCommand, FilterError};
 use super::flip::{FlipCommand, FlipError};
+use super::info::{InfoCommand, InfoError};
+use super::pipeline::{PipelineCommand, PipelineError};
 use super::resize::{ResizeCommand, ResizeError};
 use super::rotate::{RotateCommand, RotateError};
+use super::sharpen::{SharpenCommand, SharpenError};
+use super::smartcrop::{SmartCropCommand, SmartCropError};
+use super::strip::{StripCommand, StripError};
 use super::watermark::{WatermarkCommand, WatermarkError};
 
 #[derive(Subcommand)]
@@ -20,6 +28,14 @@ pub enum ImageCommand {
     #[clap(name = "convert")]
     Convert(ConvertCommand),
 
+    /// Crop an image to an area or an aspect ratio
+    #[clap(name = "crop")]
+    Crop(CropCommand),
+
+    /// Crop an image to a size, keeping its most interesting part
+    #[clap(name = "smartcrop")]
+    SmartCrop(SmartCropCommand),
+
     /// Rotate an image by an arbitrary angle
     #[clap(name = "rotate")]
     Rotate(RotateCommand),
@@ -32,6 +48,10 @@ pub enum ImageCommand {
     #[clap(name = "blur")]
     Blur(BlurCommand),
 
+    /// Sharpen an image with an unsharp mask or a convolution
+    #[clap(name = "sharpen")]
+    Sharpen(SharpenCommand),
+
     /// Flip an image
     #[clap(name = "flip")]
     Flip(FlipCommand),
@@ -39,6 +59,26 @@ pub enum ImageCommand {
     /// Brighten an image
     #[clap(name = "brighten")]
     Brightness(BrightnessCommand),
+
+    /// Adjust exposure, contrast, saturation, hue, gamma, levels and curves
+    #[clap(name = "adjust")]
+    Adjust(AdjustCommand),
+
+    /// Apply a color filter such as grayscale, sepia or duotone
+    #[clap(name = "filter")]
+    Filter(FilterCommand),
+
+    /// Apply several operations to an image, saving it only once
+    #[clap(name = "pipeline")]
+    Pipeline(PipelineCommand),
+
+    /// Remove EXIF, XMP and other metadata, such as GPS and camera data
+    #[clap(name = "strip")]
+    Strip(StripCommand),
+
+    /// Show the format, dimensions, colors and metadata of an image
+    #[clap(name = "info")]
+    Info(InfoCommand),
 }
 
 impl std::fmt::Display for ImageCommand {
@@ -46,11 +86,19 @@ impl std::fmt::Display for ImageCommand {
         match self {
             ImageCommand::Resize(_) => write!(f, "resize"),
             ImageCommand::Convert(_) => write!(f, "convert"),
+            ImageCommand::Crop(_) => write!(f, "crop"),
+            ImageCommand::SmartCrop(_) => write!(f, "smartcrop"),
             ImageCommand::Rotate(_) => write!(f, "rotate"),
             ImageCommand::Watermark(_) => write!(f, "watermark"),
             ImageCommand::Blur(_) => write!(f, "blur"),
+            ImageCommand::Sharpen(_) => write!(f, "sharpen"),
             ImageCommand::Flip(_) => write!(f, "flip"),
             ImageCommand::Brightness(_) => write!(f, "brightness"),
+            ImageCommand::Adjust(_) => write!(f, "adjust"),
+            ImageCommand::Filter(_) => write!(f, "filter"),
+            ImageCommand::Pipeline(_) => write!(f, "pipeline"),
+            ImageCommand::Strip(_) => write!(f, "strip"),
+            ImageCommand::Info(_) => write!(f, "info"),
         }
     }
 }
@@ -62,15 +110,31 @@ pub enum ImageError {
     #[error("{0}")]
     ConvertError(ConvertError),
     #[error("{0}")]
+    CropError(CropError),
+    #[error("{0}")]
+    SmartCropError(SmartCropError),
+    #[error("{0}")]
     RotateError(RotateError),
     #[error("{0}")]
     WatermarkError(WatermarkError),
     #[error("{0}")]
     BlurError(BlurError),
     #[error("{0}")]
+    SharpenError(SharpenError),
+    #[error("{0}")]
     FlipError(FlipError),
     #[error("{0}")]
     BrightnessError(BrightnessError),
+    #[error("{0}")]
+    AdjustError(AdjustError),
+    #[error("{0}")]
+    FilterError(FilterError),
+    #[error("{0}")]
+    PipelineError(PipelineError),
+    #[error("{0}")]
+    StripError(StripError),
+    #[error("{0}")]
+    InfoError(InfoError),
     #[error("No input file provided")]
     NoInputError,
     #[error("Function not implemented")]
@@ -91,6 +155,14 @@ impl ImageCommand {
                     .execute(&input)
                     .map_err(|e| ImageError::ConvertError(e)),
 
+                ImageCommand::Crop(crop) => {
+                    crop.execute(&input).map_err(|e| ImageError::CropError(e))
+                }
+
+                ImageCommand::SmartCrop(smartcrop) => smartcrop
+                    .execute(&input)
+                    .map_err(|e| ImageError::SmartCropError(e)),
+
                 ImageCommand::Rotate(rotate) => rotate
                     .execute(&input)
                     .map_err(|e| ImageError::RotateError(e)),
@@ -103,6 +175,10 @@ impl ImageCommand {
                     blur.execute(&input).map_err(|e| ImageError::BlurError(e))
                 }
 
+                ImageCommand::Sharpen(sharpen) => sharpen
+                    .execute(&input)
+                    .map_err(|e| ImageError::SharpenError(e)),
+
                 ImageCommand::Flip(flip) => {
                     flip.execute(&input).map_err(|e| ImageError::FlipError(e))
                 }
@@ -110,6 +186,26 @@ impl ImageCommand {
                 ImageCommand::Brightness(brightness) => brightness
                     .execute(&input)
                     .map_err(|e| ImageError::BrightnessError(e)),
+
+                ImageCommand::Adjust(adjust) => adjust
+                    .execute(&input)
+                    .map_err(|e| ImageError::AdjustError(e)),
+
+                ImageCommand::Filter(filter) => filter
+                    .execute(&input)
+                    .map_err(|e| ImageError::FilterError(e)),
+
+                ImageCommand::Pipeline(pipeline) => pipeline
+                    .execute(&input)
+                    .map_err(|e| ImageError::PipelineError(e)),
+
+                ImageCommand::Strip(strip) => {
+                    strip.execute(&input).map_err(|e| ImageError::StripError(e))
+                }
+
+                ImageCommand::Info(info) => {
+                    info.execute(&input).map_err(|e| ImageError::InfoError(e))
+                }
             },
             None => Err(ImageError::NoInputError),
         }
@@ -0,0 +1,139 @@
+use clap::ValueEnum;
+use image::{DynamicImage, Rgba, RgbaImage};
+
+use super::utils as image_utils;
+
+/// How the colors of an overlay mix with the colors below it, as defined by
+/// the W3C Compositing and Blending specification
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum BlendMode {
+    /// The overlay covers the image
+    Normal,
+    /// Darkens, white parts of the overlay disappear
+    Multiply,
+    /// Lightens, black parts of the overlay disappear
+    Screen,
+    /// Multiplies dark parts and screens light parts of the image
+    Overlay,
+    /// A gentler overlay, like shining a diffused light on the image
+    SoftLight,
+}
+
+impl BlendMode {
+    /// Mixes a backdrop and a source channel, both between 0 and 1
+    fn blend(self, backdrop: f32, source: f32) -> f32 {
+        return match self {
+            BlendMode::Normal => source,
+            BlendMode::Multiply => backdrop * source,
+            BlendMode::Screen => backdrop + source - backdrop * source,
+            BlendMode::Overlay => {
+                if backdrop <= 0.5 {
+                    source * 2.0 * backdrop
+                } else {
+                    let backdrop = 2.0 * backdrop - 1.0;
+                    source + backdrop - source * backdrop
+                }
+            }
+            BlendMode::SoftLight => {
+                if source <= 0.5 {
+                    backdrop - (1.0 - 2.0 * source) * backdrop * (1.0 - backdrop)
+                } else {
+                    let darkened = if backdrop <= 0.25 {
+                        ((16.0 * backdrop - 12.0) * backdrop + 4.0) * backdrop
+                    } else {
+                        backdrop.sqrt()
+                    };
+
+                    backdrop + (2.0 * source - 1.0) * (darkened - backdrop)
+                }
+            }
+        };
+    }
+}
+
+/// Porter-Duff source over of a single pixel, with the source alpha scaled
+/// by the opacity
+pub fn composite_pixel(
+    backdrop: Rgba<u8>,
+    source: Rgba<u8>,
+    opacity: f32,
+    mode: BlendMode,
+) -> Rgba<u8> {
+    let composited = composite_color(
+        backdrop.0.map(|channel| channel as f32 / 255.0),
+        source.0.map(|channel| channel as f32 / 255.0),
+        opacity,
+        mode,
+    );
+
+    return Rgba(composited.map(|channel| (channel * 255.0).round().clamp(0.0, 255.0) as u8));
+}
+
+/// Porter-Duff source over of channels between 0 and 1. Colors are blended
+/// first, then composited with premultiplied alpha so transparent pixels do
+/// not darken their neighbours.
+fn composite_color(
+    backdrop: [f32; 4],
+    source: [f32; 4],
+    opacity: f32,
+    mode: BlendMode,
+) -> [f32; 4] {
+    let source_alpha = source[3] * opacity.clamp(0.0, 1.0);
+
+    if source_alpha <= 0.0 {
+        return backdrop;
+    }
+
+    let backdrop_alpha = backdrop[3];
+    let alpha = source_alpha + backdrop_alpha * (1.0 - source_alpha);
+
+    let mut composited = [0f32; 4];
+
+    for channel in 0..3 {
+        // Where the backdrop is transparent the source keeps its own color
+        let blended = (1.0 - backdrop_alpha) * source[channel]
+            + backdrop_alpha * mode.blend(backdrop[channel], source[channel]);
+
+        let premultiplied =
+            source_alpha * blended + (1.0 - source_alpha) * backdrop_alpha * backdrop[channel];
+
+        composited[channel] = (premultiplied / alpha).clamp(0.0, 1.0);
+    }
+
+    composited[3] = alpha;
+
+    return composited;
+}
+
+/// Composites an overlay onto an image with its top left corner at the given
+/// position. The image keeps its color type and bit depth, parts of the
+/// overlay outside of it are ignored.
+pub fn composite(
+    img: &mut DynamicImage,
+    overlay: &RgbaImage,
+    x: u32,
+    y: u32,
+    opacity: f32,
+    mode: BlendMode,
+) {
+    let mut pixels = img.to_rgba32f();
+    let (width, height) = pixels.dimensions();
+
+    for (overlay_x, overlay_y, pixel) in overlay.enumerate_pixels() {
+        let (target_x, target_y) = (x + overlay_x, y + overlay_y);
+
+        if target_x >= width || target_y >= height || pixel[3] == 0 {
+            continue;
+        }
+
+        let backdrop = pixels.get_pixel_mut(target_x, target_y);
+        backdrop.0 = composite_color(
+            backdrop.0,
+            pixel.0.map(|channel| channel as f32 / 255.0),
+            opacity,
+            mode,
+        );
+    }
+
+    *img = image_utils::to_color_type(DynamicImage::ImageRgba32F(pixels), img.color());
+}
@@ -1,14 +1,30 @@
+use std::path;
+
 use clap::Args;
 
 use thiserror::Error;
 
+use super::batch::{self, BatchOperation};
+use super::{metadata, output};
 use crate::internal::utils;
 
 #[derive(Args)]
 pub struct ConvertCommand {
-    /// Output file
+    /// Output path. A directory when the input is a directory or a glob
     #[clap(short, long)]
     output: String,
+
+    /// Extension of the converted images, such as "webp", when the input is
+    /// a directory or a glob. Replaces the extension of {name} and {ext} in
+    /// the template
+    #[clap(long)]
+    format: Option<String>,
+
+    #[clap(flatten)]
+    output_options: output::OutputOptions,
+
+    #[clap(flatten)]
+    batch: batch::BatchOptions,
 }
 
 #[derive(Debug, Error)]
@@ -19,20 +35,76 @@ pub enum ConvertError {
     CrateError(image::ImageError),
     #[error("Unsupported format")]
     UnsupportedFormat,
+    #[error(
+        "Converting several images requires a --format or a --template with the new extension"
+    )]
+    MissingFormatError,
+    #[error("{0}")]
+    BatchError(batch::BatchError),
 }
 
 impl ConvertCommand {
     pub fn execute(&self, input: &str) -> Result<(), ConvertError> {
+        if batch::is_batch_input(input) {
+            let batch_options = self.get_batch_options()?;
+
+            return batch::execute(self, input, &self.output, &batch_options)
+                .map_err(|e| ConvertError::BatchError(e));
+        }
+
         let input_path = utils::to_absolute_path(input);
         let output_path = utils::to_absolute_path(&self.output);
 
-        let img = image::open(input_path.clone()).map_err(|e| ConvertError::CrateError(e))?;
-
-        img.save(&output_path)
-            .map_err(|e| ConvertError::CrateError(e))?;
+        self.process(&input_path, &output_path)?;
 
         println!("Image saved to {}", output_path.display());
 
         return Ok(());
     }
+
+    /// Batch options whose template gives the converted images the extension
+    /// of the requested format. Templates that keep the source extension are
+    /// refused, as they would save the images in their original format.
+    fn get_batch_options(&self) -> Result<batch::BatchOptions, ConvertError> {
+        let mut options = self.batch.clone();
+        let keeps_extension =
+            options.template.contains("{name}") || options.template.contains("{ext}");
+
+        match &self.format {
+            Some(format) => {
+                let extension = format.trim_start_matches('.');
+
+                if image::ImageFormat::from_extension(extension).is_none() {
+                    return Err(ConvertError::UnsupportedFormat);
+                }
+
+                options.template = options
+                    .template
+                    .replace("{name}", "{stem}.{ext}")
+                    .replace("{ext}", extension);
+            }
+            None if keeps_extension => return Err(ConvertError::MissingFormatError),
+            None => {}
+        }
+
+        return Ok(options);
+    }
+}
+
+impl BatchOperation for ConvertCommand {
+    type Error = ConvertError;
+
+    fn process(
+        &self,
+        input_path: &path::Path,
+        output_path: &path::Path,
+    ) -> Result<(), ConvertError> {
+        let (img, metadata) =
+            metadata::open(input_path).map_err(|e| ConvertError::CrateError(e))?;
+
+        output::save(&img, output_path, &metadata, &self.output_options)
+            .map_err(|e| ConvertError::CrateError(e))?;
+
+        return Ok(());
+    }
 }
@@ -0,0 +1,182 @@
+use std::path;
+use std::str::FromStr;
+
+use clap::Args;
+use thiserror::Error;
+
+use super::batch::{self, BatchOperation};
+use super::utils::Length;
+use super::watermark::WatermarkPosition;
+use super::{metadata, output};
+use crate::internal::utils;
+
+#[derive(Args)]
+pub struct CropCommand {
+    /// Horizontal offset of the crop, in pixels (100) or percent of the
+    /// width (10%). Replaces the horizontal gravity when set
+    #[clap(short, long)]
+    x: Option<Length>,
+
+    /// Vertical offset of the crop, in pixels (100) or percent of the height
+    /// (10%). Replaces the vertical gravity when set
+    #[clap(short, long)]
+    y: Option<Length>,
+
+    /// Width of the crop, in pixels (100) or percent of the width (50%).
+    /// Defaults to the whole width
+    #[clap(short, long, conflicts_with = "aspect_ratio")]
+    width: Option<Length>,
+
+    /// Height of the crop, in pixels (100) or percent of the height (50%).
+    /// Defaults to the whole height
+    #[clap(short('H'), long, conflicts_with = "aspect_ratio")]
+    height: Option<Length>,
+
+    /// Crop the largest area with this aspect ratio, such as 16:9 or 1:1
+    #[clap(short, long)]
+    aspect_ratio: Option<AspectRatio>,
+
+    /// Where to place the crop when no offset is given
+    #[clap(short, long, default_value = "center")]
+    gravity: WatermarkPosition,
+
+    /// Output path. A directory when the input is a directory or a glob
+    #[clap(short, long)]
+    output: String,
+
+    #[clap(flatten)]
+    output_options: output::OutputOptions,
+
+    #[clap(flatten)]
+    batch: batch::BatchOptions,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct AspectRatio {
+    width: f32,
+    height: f32,
+}
+
+#[derive(Debug, Error)]
+pub enum CropError {
+    #[error("{0}")]
+    ImageCrateError(image::ImageError),
+    #[error("Crop area is empty")]
+    EmptyCropError,
+    #[error("Crop area of {2}x{3} at {0},{1} does not fit inside the image of {4}x{5}")]
+    OutOfBoundsError(u32, u32, u32, u32, u32, u32),
+    #[error("{0}")]
+    BatchError(batch::BatchError),
+}
+
+impl CropCommand {
+    pub fn execute(&self, input: &str) -> Result<(), CropError> {
+        if batch::is_batch_input(input) {
+            return batch::execute(self, input, &self.output, &self.batch)
+                .map_err(|e| CropError::BatchError(e));
+        }
+
+        let input_path = utils::to_absolute_path(input);
+        let output_path = utils::to_absolute_path(&self.output);
+
+        self.process(&input_path, &output_path)?;
+
+        println!("Image saved to {}", output_path.display());
+
+        return Ok(());
+    }
+
+    /// Size of the crop area for an image of the given dimensions
+    fn get_crop_size(&self, image_width: u32, image_height: u32) -> (u32, u32) {
+        if let Some(ratio) = self.aspect_ratio {
+            let target = ratio.width / ratio.height;
+
+            if image_width as f32 / image_height as f32 > target {
+                return ((image_height as f32 * target).round() as u32, image_height);
+            }
+
+            return (image_width, (image_width as f32 / target).round() as u32);
+        }
+
+        let width = self
+            .width
+            .map(|w| w.to_pixels(image_width))
+            .unwrap_or(image_width);
+        let height = self
+            .height
+            .map(|h| h.to_pixels(image_height))
+            .unwrap_or(image_height);
+
+        return (width, height);
+    }
+}
+
+impl BatchOperation for CropCommand {
+    type Error = CropError;
+
+    fn process(&self, input_path: &path::Path, output_path: &path::Path) -> Result<(), CropError> {
+        let (img, metadata) =
+            metadata::open(input_path).map_err(|e| CropError::ImageCrateError(e))?;
+        let (image_width, image_height) = (img.width(), img.height());
+
+        let (width, height) = self.get_crop_size(image_width, image_height);
+
+        if width == 0 || height == 0 {
+            return Err(CropError::EmptyCropError);
+        }
+
+        let (gravity_x, gravity_y) = self
+            .gravity
+            .get_offset((image_width, image_height), (width, height));
+
+        let x = self
+            .x
+            .map(|x| x.to_pixels(image_width))
+            .unwrap_or(gravity_x);
+        let y = self
+            .y
+            .map(|y| y.to_pixels(image_height))
+            .unwrap_or(gravity_y);
+
+        if x as u64 + width as u64 > image_width as u64
+            || y as u64 + height as u64 > image_height as u64
+        {
+            return Err(CropError::OutOfBoundsError(
+                x,
+                y,
+                width,
+                height,
+                image_width,
+                image_height,
+            ));
+        }
+
+        output::save(
+            &img.crop_imm(x, y, width, height),
+            output_path,
+            &metadata,
+            &self.output_options,
+        )
+        .map_err(|e| CropError::ImageCrateError(e))?;
+
+        return Ok(());
+    }
+}
+
+impl FromStr for AspectRatio {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Expected an aspect ratio such as 16:9: {}", value);
+
+        let (width, height) = value.split_once(':').ok_or_else(invalid)?;
+        let width: f32 = width.trim().parse().map_err(|_| invalid())?;
+        let height: f32 = height.trim().parse().map_err(|_| invalid())?;
+
+        if width <= 0.0 || height <= 0.0 {
+            return Err(invalid());
+        }
+
+        return Ok(Self { width, height });
+    }
+}
@@ -0,0 +1,189 @@
+use std::path;
+
+use clap::{Args, ValueEnum};
+use image::DynamicImage;
+use thiserror::Error;
+
+use super::batch::{self, BatchOperation};
+use super::utils as image_utils;
+use super::{metadata, output};
+use crate::internal::utils;
+
+#[derive(Args)]
+pub struct FilterCommand {
+    /// Color filter to apply
+    #[clap(short, long)]
+    effect: ColorFilter,
+
+    /// How much each channel counts towards the gray tone of grayscale,
+    /// duotone and threshold
+    #[clap(long, default_value = "rec709")]
+    weights: LumaWeights,
+
+    /// Number of tones kept per channel by posterize
+    #[clap(long, default_value = "4", value_parser = clap::value_parser!(u8).range(2..))]
+    levels: u8,
+
+    /// Color of the shadows in duotone. Format: (r, g, b, a), a lower alpha
+    /// lets more of the original color through
+    #[clap(long, default_value = "(20, 30, 80, 255)")]
+    dark_color: String,
+
+    /// Color of the highlights in duotone. Format: (r, g, b, a), a lower
+    /// alpha lets more of the original color through
+    #[clap(long, default_value = "(255, 220, 150, 255)")]
+    light_color: String,
+
+    /// Gray tone between 0 and 255 from which threshold turns pixels white
+    #[clap(long, default_value = "128")]
+    threshold: u8,
+
+    /// Output path. A directory when the input is a directory or a glob
+    #[clap(short, long)]
+    output: String,
+
+    #[clap(flatten)]
+    output_options: output::OutputOptions,
+
+    #[clap(flatten)]
+    batch: batch::BatchOptions,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum ColorFilter {
+    /// Shades of gray
+    Grayscale,
+    /// Warm brown tones of old photographs
+    Sepia,
+    /// Negative of the image
+    Invert,
+    /// Reduce each channel to a few flat tones
+    Posterize,
+    /// Map the gray tones between a dark and a light color
+    Duotone,
+    /// Only black and white
+    Threshold,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum LumaWeights {
+    /// HDTV weights, close to how bright colors look on current screens
+    Rec709,
+    /// SDTV weights, as used by JPEG
+    Rec601,
+    /// Same weight for each channel
+    Average,
+}
+
+#[derive(Debug, Error)]
+pub enum FilterError {
+    #[error("Image crate error")]
+    ImageCrateError(image::ImageError),
+    #[error("Parse error")]
+    ParseError,
+    #[error("{0}")]
+    BatchError(batch::BatchError),
+}
+
+impl LumaWeights {
+    fn get_weights(self) -> [f32; 3] {
+        return match self {
+            LumaWeights::Rec709 => image_utils::LUMA_WEIGHTS,
+            LumaWeights::Rec601 => [0.299, 0.587, 0.114],
+            LumaWeights::Average => [1.0 / 3.0; 3],
+        };
+    }
+}
+
+impl FilterCommand {
+    pub fn execute(&self, input: &str) -> Result<(), FilterError> {
+        if batch::is_batch_input(input) {
+            return batch::execute(self, input, &self.output, &self.batch)
+                .map_err(|e| FilterError::BatchError(e));
+        }
+
+        let input_path = utils::to_absolute_path(input);
+        let output_path = utils::to_absolute_path(&self.output);
+
+        self.process(&input_path, &output_path)?;
+
+        println!("Image saved to {}", output_path.display());
+
+        return Ok(());
+    }
+
+    /// Applies the filter to each pixel, leaving the alpha channel untouched
+    fn filter(&self, img: &DynamicImage) -> Result<DynamicImage, FilterError> {
+        let to_color = |color: &str| {
+            return image_utils::from_str_to_rgba(color)
+                .map(|color| color.0.map(|channel| channel as f32 / 255.0))
+                .map_err(|_e| FilterError::ParseError);
+        };
+
+        let dark = to_color(&self.dark_color)?;
+        let light = to_color(&self.light_color)?;
+        let weights = self.weights.get_weights();
+        let steps = (self.levels - 1) as f32;
+        let threshold = self.threshold as f32 / 255.0;
+
+        let mut pixels = img.to_rgba32f();
+
+        for pixel in pixels.pixels_mut() {
+            let [red, green, blue, _] = pixel.0;
+            let gray = weights[0] * red + weights[1] * green + weights[2] * blue;
+
+            let filtered = match self.effect {
+                ColorFilter::Grayscale => [gray; 3],
+                ColorFilter::Sepia => [
+                    0.393 * red + 0.769 * green + 0.189 * blue,
+                    0.349 * red + 0.686 * green + 0.168 * blue,
+                    0.272 * red + 0.534 * green + 0.131 * blue,
+                ],
+                ColorFilter::Invert => [1.0 - red, 1.0 - green, 1.0 - blue],
+                ColorFilter::Posterize => {
+                    [red, green, blue].map(|channel| (channel * steps).round() / steps)
+                }
+                ColorFilter::Duotone => {
+                    let gray = gray.clamp(0.0, 1.0);
+                    let tone = [0, 1, 2, 3]
+                        .map(|channel| dark[channel] + (light[channel] - dark[channel]) * gray);
+
+                    let color = [red, green, blue];
+
+                    [0, 1, 2]
+                        .map(|channel| color[channel] + (tone[channel] - color[channel]) * tone[3])
+                }
+                ColorFilter::Threshold => [if gray >= threshold { 1.0 } else { 0.0 }; 3],
+            };
+
+            for (channel, value) in filtered.iter().enumerate() {
+                pixel[channel] = value.clamp(0.0, 1.0);
+            }
+        }
+
+        return Ok(image_utils::to_color_type(
+            DynamicImage::ImageRgba32F(pixels),
+            img.color(),
+        ));
+    }
+}
+
+impl BatchOperation for FilterCommand {
+    type Error = FilterError;
+
+    fn process(
+        &self,
+        input_path: &path::Path,
+        output_path: &path::Path,
+    ) -> Result<(), FilterError> {
+        let (img, metadata) =
+            metadata::open(input_path).map_err(|e| FilterError::ImageCrateError(e))?;
+
+        let filtered = self.filter(&img)?;
+
+        output::save(&filtered, output_path, &metadata, &self.output_options)
+            .map_err(|e| FilterError::ImageCrateError(e))?;
+
+        return Ok(());
+    }
+}
@@ -1,7 +1,11 @@
+use std::path;
+
 use clap::Args;
 use log::info;
 use thiserror::Error;
 
+use super::batch::{self, BatchOperation};
+use super::{metadata, output};
 use crate::internal::utils;
 
 #[derive(Args)]
@@ -14,23 +18,49 @@ pub struct FlipCommand {
     #[clap(short, long)]
     vertical: bool,
 
-    /// Output file
+    /// Output path. A directory when the input is a directory or a glob
     #[clap(short, long)]
     output: String,
+
+    #[clap(flatten)]
+    output_options: output::OutputOptions,
+
+    #[clap(flatten)]
+    batch: batch::BatchOptions,
 }
 
 #[derive(Debug, Error)]
 pub enum FlipError {
     #[error("{0}")]
     CrateError(image::ImageError),
+    #[error("{0}")]
+    BatchError(batch::BatchError),
 }
 
 impl FlipCommand {
     pub fn execute(&self, input: &str) -> Result<(), FlipError> {
+        if batch::is_batch_input(input) {
+            return batch::execute(self, input, &self.output, &self.batch)
+                .map_err(|e| FlipError::BatchError(e));
+        }
+
         let input_path = utils::to_absolute_path(input);
         let output_path = utils::to_absolute_path(&self.output);
 
-        let mut img = image::open(input_path.clone()).map_err(|e| FlipError::CrateError(e))?;
+        self.process(&input_path, &output_path)?;
+
+        println!("Image saved to {}", output_path.display());
+
+        return Ok(());
+    }
+}
+
+impl BatchOperation for FlipCommand {
+    type Error = FlipError;
+
+    fn process(&self, input_path: &path::Path, output_path: &path::Path) -> Result<(), FlipError> {
+        let (mut img, metadata) =
+            metadata::open(input_path).map_err(|e| FlipError::CrateError(e))?;
 
         if self.horizontal {
             info!("Flipping horizontally");
@@ -42,11 +72,9 @@ impl FlipCommand {
             img = img.flipv();
         }
 
-        img.save(&output_path)
+        output::save(&img, output_path, &metadata, &self.output_options)
             .map_err(|e| FlipError::CrateError(e))?;
 
-        println!("Image saved to {}", output_path.display());
-
         return Ok(());
     }
 }
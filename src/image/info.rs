@@ -0,0 +1,167 @@
+use std::collections::BTreeMap;
+use std::{fs, io};
+
+use clap::Args;
+use image::{ColorType, ImageFormat};
+use serde::Serialize;
+use thiserror::Error;
+
+use super::metadata::Metadata;
+use crate::internal::utils;
+
+#[derive(Args)]
+pub struct InfoCommand {
+    /// Print the information as JSON
+    #[clap(long)]
+    json: bool,
+}
+
+#[derive(Debug, Error)]
+pub enum InfoError {
+    #[error("{0}")]
+    IoError(std::io::Error),
+    #[error("{0}")]
+    ImageCrateError(image::ImageError),
+    #[error("{0}")]
+    JsonError(serde_json::Error),
+    #[error("Unknown image format")]
+    UnknownFormatError,
+}
+
+#[derive(Serialize)]
+struct ImageInfo {
+    format: String,
+    width: u32,
+    height: u32,
+    color_type: String,
+    bit_depth: u16,
+    has_alpha: bool,
+    file_size: u64,
+    icc_profile: Option<String>,
+    exif: BTreeMap<String, String>,
+}
+
+impl InfoCommand {
+    pub fn execute(&self, input: &str) -> Result<(), InfoError> {
+        let input_path = utils::to_absolute_path(input);
+
+        let bytes = fs::read(&input_path).map_err(|e| InfoError::IoError(e))?;
+
+        let reader = image::io::Reader::new(io::Cursor::new(&bytes))
+            .with_guessed_format()
+            .map_err(|e| InfoError::IoError(e))?;
+
+        let format = match reader.format() {
+            Some(format) => format,
+            None => {
+                ImageFormat::from_path(&input_path).map_err(|_| InfoError::UnknownFormatError)?
+            }
+        };
+
+        let img = reader.decode().map_err(|e| InfoError::ImageCrateError(e))?;
+        let color = img.color();
+        let metadata = Metadata::from_bytes(&bytes);
+
+        let info = ImageInfo {
+            format: format!("{:?}", format),
+            width: img.width(),
+            height: img.height(),
+            color_type: get_color_name(color).to_string(),
+            bit_depth: color.bits_per_pixel() / color.channel_count() as u16,
+            has_alpha: color.has_alpha(),
+            file_size: bytes.len() as u64,
+            icc_profile: metadata.icc_description(),
+            exif: get_exif_fields(&metadata),
+        };
+
+        if self.json {
+            let json = serde_json::to_string_pretty(&info).map_err(|e| InfoError::JsonError(e))?;
+            println!("{}", json);
+
+            return Ok(());
+        }
+
+        println!("Format: {}", info.format);
+        println!("Dimensions: {}x{}", info.width, info.height);
+        println!("Color type: {}", info.color_type);
+        println!("Bit depth: {}", info.bit_depth);
+        println!("Alpha: {}", if info.has_alpha { "yes" } else { "no" });
+        println!("File size: {}", format_size(info.file_size));
+        println!(
+            "ICC profile: {}",
+            info.icc_profile.as_deref().unwrap_or("none")
+        );
+
+        if info.exif.is_empty() {
+            println!("EXIF: none");
+        } else {
+            println!("EXIF:");
+
+            for (tag, value) in info.exif.iter() {
+                println!("  {}: {}", tag, value);
+            }
+        }
+
+        return Ok(());
+    }
+}
+
+fn get_color_name(color: ColorType) -> &'static str {
+    return match color.channel_count() {
+        1 => "Grayscale",
+        2 => "Grayscale with alpha",
+        3 => "RGB",
+        _ => "RGBA",
+    };
+}
+
+/// Readable values of the EXIF fields describing the image itself, leaving
+/// out the thumbnail, the maker notes and the pointers between directories
+fn get_exif_fields(metadata: &Metadata) -> BTreeMap<String, String> {
+    let mut fields = BTreeMap::new();
+
+    let exif = match &metadata.exif {
+        Some(exif) => match exif::Reader::new().read_raw(exif.to_vec()) {
+            Ok(exif) => exif,
+            Err(_) => return fields,
+        },
+        None => return fields,
+    };
+
+    for field in exif.fields() {
+        if field.ifd_num != exif::In::PRIMARY {
+            continue;
+        }
+
+        match field.tag {
+            exif::Tag::MakerNote
+            | exif::Tag::ExifIFDPointer
+            | exif::Tag::GPSInfoIFDPointer
+            | exif::Tag::InteropIFDPointer => continue,
+            _ => {}
+        }
+
+        let value = field.display_value().with_unit(&exif).to_string();
+        fields.insert(field.tag.to_string(), value.trim_matches('"').to_string());
+    }
+
+    return fields;
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 3] = ["KiB", "MiB", "GiB"];
+
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    return format!("{:.1} {} ({} bytes)", size, UNITS[unit], bytes);
+}
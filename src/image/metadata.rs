@@ -0,0 +1,407 @@
+use std::{fs, io, path};
+
+use image::{DynamicImage, ImageError, ImageFormat};
+use img_parts::jpeg::{markers, JpegSegment};
+use img_parts::png::PngChunk;
+use img_parts::{webp, Bytes, DynImage, ImageEXIF, ImageICC};
+use log::warn;
+
+const ORIENTATION_TAG: u16 = 0x0112;
+const ICC_HEADER_SIZE: usize = 128;
+const SHORT_TYPE: u16 = 3;
+
+/// Header of the APP1 segment holding XMP in JPEG files
+const XMP_JPEG_PREFIX: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+/// Keyword of the iTXt chunk holding XMP in PNG files, followed by an
+/// uncompressed flag, a compression method and empty language and translated
+/// keyword fields
+const XMP_PNG_PREFIX: &[u8] = b"XML:com.adobe.xmp\0\0\0\0\0";
+const XMP_PNG_KEYWORD: &[u8] = b"XML:com.adobe.xmp\0";
+const ICC_JPEG_PREFIX: &[u8] = b"ICC_PROFILE\0";
+/// PNG chunks holding text, XMP, EXIF or the modification time
+const PNG_TEXT_CHUNKS: &[[u8; 4]] = &[*b"tEXt", *b"zTXt", *b"iTXt", *b"eXIf", *b"tIME"];
+
+/// EXIF, ICC profile and XMP packet of an image, carried over from the input
+/// to the output so operations do not lose them
+#[derive(Clone, Default)]
+pub struct Metadata {
+    pub exif: Option<Bytes>,
+    pub icc: Option<Bytes>,
+    pub xmp: Option<Bytes>,
+}
+
+impl Metadata {
+    /// Reads the metadata of a JPEG, PNG or WebP file. Other formats, and
+    /// files the metadata could not be parsed from, have none.
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let container = match DynImage::from_bytes(Bytes::copy_from_slice(bytes)) {
+            Ok(Some(container)) => container,
+            Ok(None) => return Self::default(),
+            Err(e) => {
+                warn!("Could not read image metadata: {}", e);
+                return Self::default();
+            }
+        };
+
+        let xmp = match &container {
+            DynImage::Jpeg(jpeg) => jpeg
+                .segments_by_marker(markers::APP1)
+                .find(|segment| segment.contents().starts_with(XMP_JPEG_PREFIX))
+                .map(|segment| segment.contents().slice(XMP_JPEG_PREFIX.len()..)),
+            DynImage::Png(png) => png
+                .chunks_by_type(*b"iTXt")
+                .find_map(|chunk| get_png_xmp(chunk.contents())),
+            DynImage::WebP(webp) => webp
+                .chunk_by_id(webp::CHUNK_XMP)
+                .and_then(|chunk| chunk.content().data().cloned()),
+        };
+
+        return Self {
+            exif: container.exif(),
+            icc: container.icc_profile(),
+            xmp,
+        };
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.exif.is_none() && self.icc.is_none() && self.xmp.is_none();
+    }
+
+    /// Value of the EXIF orientation tag, between 1 and 8
+    pub fn orientation(&self) -> Option<u16> {
+        let exif = self.exif.as_ref()?;
+        let (offset, little_endian) = find_orientation(exif)?;
+
+        return read_u16(exif, offset, little_endian).filter(|value| (1..=8).contains(value));
+    }
+
+    /// Description of the ICC profile, such as "sRGB IEC61966-2.1", read
+    /// from its desc tag
+    pub fn icc_description(&self) -> Option<String> {
+        let icc = self.icc.as_ref()?;
+        let tags = read_u32(icc, ICC_HEADER_SIZE, false)? as usize;
+
+        for index in 0..tags {
+            let entry = ICC_HEADER_SIZE + 4 + index * 12;
+
+            if icc.get(entry..entry + 4)? != b"desc" {
+                continue;
+            }
+
+            let offset = read_u32(icc, entry + 4, false)? as usize;
+            let data = icc.get(offset..)?;
+
+            return match data.get(0..4)? {
+                // Version 2 profiles store a null terminated ASCII string
+                b"desc" => {
+                    let length = read_u32(data, 8, false)? as usize;
+                    let text = data.get(12..12 + length)?;
+
+                    Some(
+                        String::from_utf8_lossy(text)
+                            .trim_end_matches('\0')
+                            .to_string(),
+                    )
+                }
+                // Version 4 profiles store UTF-16 strings per language, the
+                // first one is used
+                b"mluc" => {
+                    let length = read_u32(data, 20, false)? as usize;
+                    let start = read_u32(data, 24, false)? as usize;
+
+                    let text = data
+                        .get(start..start + length)?
+                        .chunks_exact(2)
+                        .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                        .collect::<Vec<u16>>();
+
+                    Some(String::from_utf16_lossy(&text))
+                }
+                _ => None,
+            };
+        }
+
+        return None;
+    }
+
+    /// Replaces the EXIF orientation tag, if there is one
+    pub fn set_orientation(&mut self, orientation: u16) {
+        let exif = match &self.exif {
+            Some(exif) => exif,
+            None => return,
+        };
+
+        if let Some((offset, little_endian)) = find_orientation(exif) {
+            let value = if little_endian {
+                orientation.to_le_bytes()
+            } else {
+                orientation.to_be_bytes()
+            };
+
+            let mut exif = exif.to_vec();
+            exif[offset..offset + 2].copy_from_slice(&value);
+            self.exif = Some(exif.into());
+        }
+    }
+
+    /// Writes the metadata into an encoded JPEG or PNG file. Other formats
+    /// are returned untouched.
+    pub fn embed(&self, encoded: Bytes) -> Bytes {
+        if self.is_empty() {
+            return encoded;
+        }
+
+        let mut container = match DynImage::from_bytes(encoded.clone()) {
+            Ok(Some(container)) => container,
+            Ok(None) => return encoded,
+            Err(e) => {
+                warn!("Could not write image metadata: {}", e);
+                return encoded;
+            }
+        };
+
+        container.set_exif(self.exif.clone());
+        container.set_icc_profile(self.icc.clone());
+
+        if let Some(xmp) = &self.xmp {
+            match &mut container {
+                DynImage::Jpeg(jpeg) => {
+                    let position = jpeg
+                        .segments()
+                        .iter()
+                        .position(|segment| segment.marker() == markers::APP1)
+                        .map(|position| position + 1)
+                        .unwrap_or(jpeg.segments().len().min(1));
+
+                    let contents = [XMP_JPEG_PREFIX, xmp].concat();
+                    jpeg.segments_mut().insert(
+                        position,
+                        JpegSegment::new_with_contents(markers::APP1, contents.into()),
+                    );
+                }
+                DynImage::Png(png) => {
+                    let position = png
+                        .chunks()
+                        .iter()
+                        .position(|chunk| chunk.kind() == *b"IDAT")
+                        .unwrap_or(png.chunks().len());
+
+                    let contents = [XMP_PNG_PREFIX, xmp].concat();
+                    png.chunks_mut()
+                        .insert(position, PngChunk::new(*b"iTXt", contents.into()));
+                }
+                // WebP needs the XMP flag of its extended header, which is
+                // not supported
+                DynImage::WebP(_) => warn!("XMP metadata is not written to WebP images"),
+            }
+        }
+
+        sort_parts(&mut container);
+
+        return container.encoder().bytes();
+    }
+}
+
+/// Opens an image, rotating and flipping it as its EXIF orientation says so
+/// it comes out upright. The orientation tag of the returned metadata is
+/// reset to match.
+pub fn open(path: &path::Path) -> Result<(DynamicImage, Metadata), ImageError> {
+    let bytes = fs::read(path).map_err(|e| ImageError::IoError(e))?;
+
+    let mut reader = image::io::Reader::new(io::Cursor::new(&bytes));
+
+    match ImageFormat::from_path(path) {
+        Ok(format) => reader.set_format(format),
+        Err(_) => {
+            reader = reader
+                .with_guessed_format()
+                .map_err(|e| ImageError::IoError(e))?
+        }
+    }
+
+    let img = reader.decode()?;
+    let mut metadata = Metadata::from_bytes(&bytes);
+
+    let img = match metadata.orientation() {
+        Some(orientation) if orientation != 1 => {
+            metadata.set_orientation(1);
+            apply_orientation(img, orientation)
+        }
+        _ => img,
+    };
+
+    return Ok((img, metadata));
+}
+
+/// Removes EXIF, XMP, comments and other application data from an image
+/// without decoding it. The orientation is kept in a minimal EXIF block so
+/// the image still displays upright.
+pub fn strip(container: &mut DynImage, remove_icc: bool) {
+    let orientation = Metadata {
+        exif: container.exif(),
+        ..Default::default()
+    }
+    .orientation();
+
+    match container {
+        DynImage::Jpeg(jpeg) => jpeg.segments_mut().retain(|segment| {
+            let contents = segment.contents();
+
+            return match segment.marker() {
+                markers::APP1 => false,
+                // Keep the ICC profile, which is also stored in APP2
+                markers::APP2 => contents.starts_with(ICC_JPEG_PREFIX),
+                markers::APP3..=markers::APP15 | markers::COM => false,
+                _ => true,
+            };
+        }),
+        DynImage::Png(png) => png
+            .chunks_mut()
+            .retain(|chunk| !PNG_TEXT_CHUNKS.contains(&chunk.kind())),
+        DynImage::WebP(webp) => webp.remove_chunks_by_id(webp::CHUNK_XMP),
+    }
+
+    // Setting the EXIF also updates the header flags of WebP images
+    container.set_exif(
+        orientation
+            .filter(|orientation| *orientation != 1)
+            .map(orientation_exif),
+    );
+
+    if remove_icc {
+        container.set_icc_profile(None);
+    }
+
+    sort_parts(container);
+}
+
+/// Moves the metadata added by img-parts before the image data. It can land
+/// after the frame header of JPEG files, and after the data of PNG files,
+/// where some readers do not look for it.
+fn sort_parts(container: &mut DynImage) {
+    match container {
+        DynImage::Jpeg(jpeg) => jpeg
+            .segments_mut()
+            .sort_by_key(|segment| match segment.marker() {
+                marker @ markers::APP0..=markers::APP15 => marker as u16,
+                _ => u16::MAX,
+            }),
+        DynImage::Png(png) => {
+            let data = png
+                .chunks()
+                .iter()
+                .position(|chunk| chunk.kind() == *b"IDAT")
+                .unwrap_or(0);
+
+            png.chunks_mut()[data..].sort_by_key(|chunk| match &chunk.kind() {
+                b"eXIf" => 0,
+                _ => 1,
+            });
+        }
+        DynImage::WebP(_) => {}
+    }
+}
+
+/// Turns an image stored with an EXIF orientation into its upright version
+pub fn apply_orientation(img: DynamicImage, orientation: u16) -> DynamicImage {
+    return match orientation {
+        2 => img.fliph(),
+        3 => img.rotate180(),
+        4 => img.flipv(),
+        5 => img.rotate90().fliph(),
+        6 => img.rotate90(),
+        7 => img.rotate270().fliph(),
+        8 => img.rotate270(),
+        _ => img,
+    };
+}
+
+/// Smallest EXIF block holding only an orientation tag
+pub fn orientation_exif(orientation: u16) -> Bytes {
+    let mut exif = Vec::with_capacity(26);
+
+    // Little endian TIFF header pointing to the first directory
+    exif.extend_from_slice(b"II*\0");
+    exif.extend_from_slice(&8u32.to_le_bytes());
+
+    // Directory with a single entry of one short, and no next directory
+    exif.extend_from_slice(&1u16.to_le_bytes());
+    exif.extend_from_slice(&ORIENTATION_TAG.to_le_bytes());
+    exif.extend_from_slice(&SHORT_TYPE.to_le_bytes());
+    exif.extend_from_slice(&1u32.to_le_bytes());
+    exif.extend_from_slice(&orientation.to_le_bytes());
+    exif.extend_from_slice(&[0, 0]);
+    exif.extend_from_slice(&0u32.to_le_bytes());
+
+    return exif.into();
+}
+
+/// Offset of the value of the orientation entry in the first directory of
+/// an EXIF block, and whether the block is little endian
+fn find_orientation(exif: &[u8]) -> Option<(usize, bool)> {
+    let little_endian = match exif.get(0..2)? {
+        b"II" => true,
+        b"MM" => false,
+        _ => return None,
+    };
+
+    let directory = read_u32(exif, 4, little_endian)? as usize;
+    let entries = read_u16(exif, directory, little_endian)? as usize;
+
+    for index in 0..entries {
+        let entry = directory + 2 + index * 12;
+
+        if read_u16(exif, entry, little_endian)? == ORIENTATION_TAG {
+            if read_u16(exif, entry + 2, little_endian)? != SHORT_TYPE {
+                return None;
+            }
+
+            return Some((entry + 8, little_endian));
+        }
+    }
+
+    return None;
+}
+
+fn read_u16(bytes: &[u8], offset: usize, little_endian: bool) -> Option<u16> {
+    let value: [u8; 2] = bytes.get(offset..offset + 2)?.try_into().ok()?;
+
+    return Some(if little_endian {
+        u16::from_le_bytes(value)
+    } else {
+        u16::from_be_bytes(value)
+    });
+}
+
+fn read_u32(bytes: &[u8], offset: usize, little_endian: bool) -> Option<u32> {
+    let value: [u8; 4] = bytes.get(offset..offset + 4)?.try_into().ok()?;
+
+    return Some(if little_endian {
+        u32::from_le_bytes(value)
+    } else {
+        u32::from_be_bytes(value)
+    });
+}
+
+/// XMP packet of an uncompressed iTXt chunk, if the chunk holds one
+fn get_png_xmp(contents: &Bytes) -> Option<Bytes> {
+    if !contents.starts_with(XMP_PNG_KEYWORD) {
+        return None;
+    }
+
+    let mut offset = XMP_PNG_KEYWORD.len();
+
+    // Compressed packets are rare, and would need inflating
+    if *contents.get(offset)? != 0 {
+        return None;
+    }
+
+    offset += 2;
+
+    // Skip the language tag and the translated keyword
+    for _ in 0..2 {
+        offset += contents.get(offset..)?.iter().position(|b| *b == 0)? + 1;
+    }
+
+    return Some(contents.slice(offset..));
+}
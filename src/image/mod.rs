@@ -1,12 +1,25 @@
 pub mod cli;
 
 // Actions
+pub mod adjust;
 pub mod blur;
 pub mod brightness;
 pub mod convert;
+pub mod crop;
+pub mod filter;
 pub mod flip;
+pub mod info;
+pub mod metadata;
+pub mod output;
+pub mod pipeline;
 pub mod resize;
 pub mod rotate;
+pub mod sharpen;
+pub mod smartcrop;
+pub mod strip;
 pub mod watermark;
 
+pub(crate) mod batch;
+mod composite;
+mod text;
 mod utils;
@@ -0,0 +1,225 @@
+use std::{fs, io, path};
+
+use clap::{Args, ValueEnum};
+use image::codecs::{png, pnm, webp};
+use image::error::{EncodingError, ImageFormatHint, LimitError, LimitErrorKind};
+use image::{DynamicImage, ImageEncoder, ImageError, ImageFormat};
+use jpeg_encoder::{ColorType, Encoder, SamplingFactor};
+
+use super::metadata::Metadata;
+
+/// Encoder settings shared by every image command. Each option only applies
+/// to the format it is named after, the format itself comes from the
+/// extension of the output.
+#[derive(Args, Clone)]
+pub struct OutputOptions {
+    /// Quality of JPEG output, between 1 and 100
+    #[clap(long, default_value = "90", value_parser = clap::value_parser!(u8).range(1..=100))]
+    pub quality: u8,
+
+    /// Write progressive JPEG, which loads from blurry to sharp
+    #[clap(long)]
+    pub progressive: bool,
+
+    /// Resolution of the colors of JPEG output relative to the brightness
+    #[clap(long, default_value = "420")]
+    pub chroma_subsampling: ChromaSubsampling,
+
+    /// Compression of PNG output. Better compression is slower
+    #[clap(long, default_value = "default")]
+    pub png_compression: PngCompression,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum ChromaSubsampling {
+    /// Full color resolution
+    #[value(name = "444")]
+    Full,
+    /// Half the horizontal color resolution
+    #[value(name = "422")]
+    Half,
+    /// Half the horizontal and vertical color resolution
+    #[value(name = "420")]
+    Quarter,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum PngCompression {
+    Fast,
+    Default,
+    Best,
+}
+
+impl OutputOptions {
+    /// Encodes an image with the encoder of the format of the path's
+    /// extension. WebP is always lossless, and formats without options use
+    /// the defaults of `image`.
+    pub fn encode(&self, img: &DynamicImage, path: &path::Path) -> Result<Vec<u8>, ImageError> {
+        let format = ImageFormat::from_path(path)?;
+        let mut encoded = Vec::new();
+
+        match format {
+            ImageFormat::Jpeg => self.encode_jpeg(img, &mut encoded)?,
+            ImageFormat::Png => {
+                let compression = match self.png_compression {
+                    PngCompression::Fast => png::CompressionType::Fast,
+                    PngCompression::Default => png::CompressionType::Default,
+                    PngCompression::Best => png::CompressionType::Best,
+                };
+
+                // Floating point images have no PNG equivalent
+                let converted;
+                let img = match img {
+                    DynamicImage::ImageRgb32F(_) => {
+                        converted = DynamicImage::ImageRgb16(img.to_rgb16());
+                        &converted
+                    }
+                    DynamicImage::ImageRgba32F(_) => {
+                        converted = DynamicImage::ImageRgba16(img.to_rgba16());
+                        &converted
+                    }
+                    _ => img,
+                };
+
+                png::PngEncoder::new_with_quality(
+                    &mut encoded,
+                    compression,
+                    png::FilterType::Adaptive,
+                )
+                .write_image(
+                    img.as_bytes(),
+                    img.width(),
+                    img.height(),
+                    img.color(),
+                )?;
+            }
+            ImageFormat::WebP => {
+                let encoder = webp::WebPEncoder::new_lossless(&mut encoded);
+
+                if img.color().has_alpha() {
+                    let rgba = img.to_rgba8();
+                    encoder.write_image(
+                        &rgba,
+                        rgba.width(),
+                        rgba.height(),
+                        image::ColorType::Rgba8,
+                    )?;
+                } else {
+                    let rgb = img.to_rgb8();
+                    encoder.write_image(&rgb, rgb.width(), rgb.height(), image::ColorType::Rgb8)?;
+                }
+            }
+            ImageFormat::Pnm => encode_pnm(img, path, &mut encoded)?,
+            _ => img.write_to(&mut io::Cursor::new(&mut encoded), format)?,
+        }
+
+        return Ok(encoded);
+    }
+
+    /// JPEG has no transparency, so the alpha channel is dropped
+    fn encode_jpeg(&self, img: &DynamicImage, encoded: &mut Vec<u8>) -> Result<(), ImageError> {
+        let to_image_error = |e: jpeg_encoder::EncodingError| {
+            ImageError::Encoding(EncodingError::new(
+                ImageFormatHint::Exact(ImageFormat::Jpeg),
+                e,
+            ))
+        };
+
+        // JPEG dimensions are stored on 16 bits
+        let (width, height) = match (u16::try_from(img.width()), u16::try_from(img.height())) {
+            (Ok(width), Ok(height)) => (width, height),
+            _ => {
+                return Err(ImageError::Limits(LimitError::from_kind(
+                    LimitErrorKind::DimensionError,
+                )))
+            }
+        };
+
+        let mut encoder = Encoder::new(encoded, self.quality);
+
+        encoder.set_progressive(self.progressive);
+        encoder.set_sampling_factor(match self.chroma_subsampling {
+            ChromaSubsampling::Full => SamplingFactor::R_4_4_4,
+            ChromaSubsampling::Half => SamplingFactor::R_4_2_2,
+            ChromaSubsampling::Quarter => SamplingFactor::R_4_2_0,
+        });
+
+        if img.color().channel_count() <= 2 {
+            encoder
+                .encode(&img.to_luma8(), width, height, ColorType::Luma)
+                .map_err(to_image_error)?;
+        } else {
+            encoder
+                .encode(&img.to_rgb8(), width, height, ColorType::Rgb)
+                .map_err(to_image_error)?;
+        }
+
+        return Ok(());
+    }
+}
+
+/// Saves an image in the format of its extension with the given encoder
+/// options, keeping the metadata when the format supports it
+pub fn save(
+    img: &DynamicImage,
+    path: &path::Path,
+    metadata: &Metadata,
+    options: &OutputOptions,
+) -> Result<(), ImageError> {
+    let encoded = options.encode(img, path)?;
+
+    fs::write(path, metadata.embed(encoded.into())).map_err(|e| ImageError::IoError(e))?;
+
+    return Ok(());
+}
+
+/// Each PNM extension is its own subtype, with the colors it can hold:
+/// bitmaps and graymaps are gray and pixmaps have no transparency
+fn encode_pnm(
+    img: &DynamicImage,
+    path: &path::Path,
+    encoded: &mut Vec<u8>,
+) -> Result<(), ImageError> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map_or(String::new(), |extension| extension.to_ascii_lowercase());
+
+    let (subtype, converted) = match extension.as_str() {
+        "pbm" => (
+            pnm::PnmSubtype::Bitmap(pnm::SampleEncoding::Binary),
+            DynamicImage::ImageLuma8(img.to_luma8()),
+        ),
+        "pgm" => (
+            pnm::PnmSubtype::Graymap(pnm::SampleEncoding::Binary),
+            DynamicImage::ImageLuma8(img.to_luma8()),
+        ),
+        "ppm" => (
+            pnm::PnmSubtype::Pixmap(pnm::SampleEncoding::Binary),
+            DynamicImage::ImageRgb8(img.to_rgb8()),
+        ),
+        _ if img.color().channel_count() == 1 => (
+            pnm::PnmSubtype::ArbitraryMap,
+            DynamicImage::ImageLuma8(img.to_luma8()),
+        ),
+        _ if img.color().has_alpha() => (
+            pnm::PnmSubtype::ArbitraryMap,
+            DynamicImage::ImageRgba8(img.to_rgba8()),
+        ),
+        _ => (
+            pnm::PnmSubtype::ArbitraryMap,
+            DynamicImage::ImageRgb8(img.to_rgb8()),
+        ),
+    };
+
+    pnm::PnmEncoder::new(encoded)
+        .with_subtype(subtype)
+        .write_image(
+            converted.as_bytes(),
+            converted.width(),
+            converted.height(),
+            converted.color(),
+        )?;
+
+    return Ok(());
+}
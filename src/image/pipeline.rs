@@ -0,0 +1,315 @@
+use std::path;
+
+use clap::{Arg, ArgAction, ArgMatches, Args, Command, FromArgMatches};
+use image::{DynamicImage, Rgba};
+use thiserror::Error;
+
+use super::batch::{self, BatchOperation};
+use super::composite::BlendMode;
+use super::resize::ResizeFilter;
+use super::watermark::{PlacementOptions, WatermarkError};
+use super::{blur, brightness, resize, rotate, watermark};
+use super::{metadata, output};
+use crate::internal::utils;
+
+/// Arguments that add a step to the pipeline, applied in the order they are
+/// given on the command line
+const STEPS: &[&str] = &["resize", "rotate", "flip", "blur", "brighten", "watermark"];
+
+/// Applies several operations to an image, decoding and encoding it only
+/// once. Implements `Args` by hand since the derive loses the order of the
+/// arguments.
+pub struct PipelineCommand {
+    steps: Vec<Step>,
+    filter: ResizeFilter,
+    output: String,
+    output_options: output::OutputOptions,
+    batch: batch::BatchOptions,
+}
+
+#[derive(Clone)]
+enum Step {
+    Resize(Size),
+    Rotate(f32),
+    Flip(FlipDirection),
+    Blur(f32),
+    Brighten(i32),
+    Watermark(DynamicImage),
+}
+
+#[derive(Clone)]
+enum Size {
+    Percentage(f32),
+    Pixels(u32, u32),
+}
+
+#[derive(Clone)]
+enum FlipDirection {
+    Horizontal,
+    Vertical,
+}
+
+#[derive(Debug, Error)]
+pub enum PipelineError {
+    #[error("{0}")]
+    ImageCrateError(image::ImageError),
+    #[error("{0}")]
+    WatermarkError(WatermarkError),
+    #[error("{0}")]
+    BatchError(batch::BatchError),
+}
+
+impl PipelineCommand {
+    pub fn execute(&self, input: &str) -> Result<(), PipelineError> {
+        if batch::is_batch_input(input) {
+            return batch::execute(self, input, &self.output, &self.batch)
+                .map_err(|e| PipelineError::BatchError(e));
+        }
+
+        let input_path = utils::to_absolute_path(input);
+        let output_path = utils::to_absolute_path(&self.output);
+
+        self.process(&input_path, &output_path)?;
+
+        println!("Image saved to {}", output_path.display());
+
+        return Ok(());
+    }
+}
+
+impl BatchOperation for PipelineCommand {
+    type Error = PipelineError;
+
+    fn process(
+        &self,
+        input_path: &path::Path,
+        output_path: &path::Path,
+    ) -> Result<(), PipelineError> {
+        let (mut img, metadata) =
+            metadata::open(input_path).map_err(|e| PipelineError::ImageCrateError(e))?;
+
+        for step in self.steps.iter() {
+            img = step.apply(img, self.filter)?;
+        }
+
+        output::save(&img, output_path, &metadata, &self.output_options)
+            .map_err(|e| PipelineError::ImageCrateError(e))?;
+
+        return Ok(());
+    }
+}
+
+impl Step {
+    fn apply(
+        &self,
+        img: DynamicImage,
+        filter: ResizeFilter,
+    ) -> Result<DynamicImage, PipelineError> {
+        return match self {
+            Step::Resize(Size::Percentage(percentage)) => {
+                let width = (img.width() as f32 * percentage / 100.0).round().max(1.0);
+                let height = (img.height() as f32 * percentage / 100.0).round().max(1.0);
+
+                Ok(resize::resize(
+                    &img,
+                    width as u32,
+                    height as u32,
+                    false,
+                    filter,
+                ))
+            }
+            Step::Resize(Size::Pixels(width, height)) => {
+                Ok(resize::resize(&img, *width, *height, true, filter))
+            }
+            Step::Rotate(angle) => Ok(rotate::rotate(&img, *angle, false, Rgba([0, 0, 0, 0]))),
+            Step::Flip(FlipDirection::Horizontal) => Ok(img.fliph()),
+            Step::Flip(FlipDirection::Vertical) => Ok(img.flipv()),
+            Step::Blur(intensity) => Ok(blur::blur(&img, *intensity)),
+            Step::Brighten(intensity) => Ok(brightness::brighten(&img, *intensity)),
+            Step::Watermark(overlay) => {
+                let mut img = img;
+
+                watermark::apply_watermark(
+                    &mut img,
+                    overlay.clone(),
+                    &PlacementOptions::default(),
+                    1.0,
+                    BlendMode::Normal,
+                    None,
+                    filter,
+                )
+                .map_err(|e| PipelineError::WatermarkError(e))?;
+
+                Ok(img)
+            }
+        };
+    }
+}
+
+impl FromArgMatches for PipelineCommand {
+    fn from_arg_matches(matches: &ArgMatches) -> Result<Self, clap::Error> {
+        let mut steps: Vec<(usize, Step)> = Vec::new();
+
+        for id in STEPS {
+            if let (Some(values), Some(indices)) =
+                (matches.get_many::<Step>(id), matches.indices_of(id))
+            {
+                steps.extend(indices.zip(values.cloned()));
+            }
+        }
+
+        steps.sort_by_key(|(index, _)| *index);
+
+        let filter = matches
+            .get_one::<ResizeFilter>("filter")
+            .copied()
+            .unwrap_or(ResizeFilter::Lanczos3);
+
+        let output = matches
+            .get_one::<String>("output")
+            .cloned()
+            .ok_or_else(|| clap::Error::new(clap::error::ErrorKind::MissingRequiredArgument))?;
+
+        return Ok(Self {
+            steps: steps.into_iter().map(|(_, step)| step).collect(),
+            filter,
+            output,
+            output_options: output::OutputOptions::from_arg_matches(matches)?,
+            batch: batch::BatchOptions::from_arg_matches(matches)?,
+        });
+    }
+
+    fn update_from_arg_matches(&mut self, matches: &ArgMatches) -> Result<(), clap::Error> {
+        *self = Self::from_arg_matches(matches)?;
+        return Ok(());
+    }
+}
+
+impl Args for PipelineCommand {
+    fn augment_args(command: Command) -> Command {
+        let command = command
+            .arg(
+                Arg::new("resize")
+                    .long("resize")
+                    .value_name("SIZE")
+                    .action(ArgAction::Append)
+                    .value_parser(parse_resize)
+                    .help(
+                        "Resize by a percentage (50%) or to fit inside a size in pixels \
+                         (800x600)",
+                    ),
+            )
+            .arg(
+                Arg::new("rotate")
+                    .long("rotate")
+                    .value_name("ANGLE")
+                    .action(ArgAction::Append)
+                    .allow_hyphen_values(true)
+                    .value_parser(|value: &str| parse_number(value).map(Step::Rotate))
+                    .help("Rotate clockwise by an angle in degrees"),
+            )
+            .arg(
+                Arg::new("flip")
+                    .long("flip")
+                    .value_name("DIRECTION")
+                    .action(ArgAction::Append)
+                    .value_parser(parse_flip)
+                    .help("Flip horizontally or vertically"),
+            )
+            .arg(
+                Arg::new("blur")
+                    .long("blur")
+                    .value_name("INTENSITY")
+                    .action(ArgAction::Append)
+                    .value_parser(|value: &str| parse_number(value).map(Step::Blur))
+                    .help("Blur with an intensity between 1 and 100"),
+            )
+            .arg(
+                Arg::new("brighten")
+                    .long("brighten")
+                    .value_name("INTENSITY")
+                    .action(ArgAction::Append)
+                    .allow_hyphen_values(true)
+                    .value_parser(|value: &str| parse_number(value).map(Step::Brighten))
+                    .help("Brighten, or darken with negative values, between -100 and 100"),
+            )
+            .arg(
+                Arg::new("watermark")
+                    .long("watermark")
+                    .value_name("PATH")
+                    .action(ArgAction::Append)
+                    .value_parser(|value: &str| load_overlay(value).map(Step::Watermark))
+                    .help("Overlay a picture in the center"),
+            )
+            .arg(
+                Arg::new("filter")
+                    .short('f')
+                    .long("filter")
+                    .value_name("FILTER")
+                    .default_value("lanczos3")
+                    .value_parser(clap::builder::EnumValueParser::<ResizeFilter>::new())
+                    .help("Resampling filter used by the resize steps"),
+            )
+            .arg(
+                Arg::new("output")
+                    .short('o')
+                    .long("output")
+                    .value_name("OUTPUT")
+                    .required(true)
+                    .help("Output path. A directory when the input is a directory or a glob"),
+            );
+
+        let command = output::OutputOptions::augment_args(command);
+
+        return batch::BatchOptions::augment_args(command);
+    }
+
+    fn augment_args_for_update(command: Command) -> Command {
+        return Self::augment_args(command);
+    }
+}
+
+fn parse_resize(value: &str) -> Result<Step, String> {
+    if let Some(percentage) = value.strip_suffix('%') {
+        let percentage: f32 = parse_number(percentage)?;
+
+        if percentage <= 0.0 {
+            return Err("Percentage must be positive".to_string());
+        }
+
+        return Ok(Step::Resize(Size::Percentage(percentage)));
+    }
+
+    return match value.split_once('x') {
+        Some((width, height)) => Ok(Step::Resize(Size::Pixels(
+            parse_number(width)?,
+            parse_number(height)?,
+        ))),
+        None => Err("Expected a percentage (50%) or a size (800x600)".to_string()),
+    };
+}
+
+fn parse_flip(value: &str) -> Result<Step, String> {
+    return match value {
+        "h" | "horizontal" => Ok(Step::Flip(FlipDirection::Horizontal)),
+        "v" | "vertical" => Ok(Step::Flip(FlipDirection::Vertical)),
+        _ => Err("Expected horizontal or vertical".to_string()),
+    };
+}
+
+/// Decodes a watermark once while parsing, so it is shared by every image of
+/// a batch and a missing file is reported before any image is processed
+fn load_overlay(value: &str) -> Result<DynamicImage, String> {
+    let path = utils::to_absolute_path(value);
+
+    return metadata::open(&path)
+        .map(|(overlay, _)| overlay)
+        .map_err(|e| format!("Could not open {}: {}", path.display(), e));
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    return value
+        .trim()
+        .parse()
+        .map_err(|_| format!("Invalid number: {}", value));
+}
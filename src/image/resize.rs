@@ -1,82 +1,215 @@
-use clap::Args;
-use image;
+use std::path;
+
+use clap::{Args, ValueEnum};
+use image::{self, imageops, DynamicImage, GenericImageView, Rgba, RgbaImage};
 use thiserror::Error;
 
+use super::batch::{self, BatchOperation};
+use super::utils as image_utils;
+use super::{metadata, output};
+use crate::internal::resize::{self as resize_plan, ResizeMode, ResizePlan};
 use crate::internal::utils;
 
 #[derive(Args)]
 pub struct ResizeCommand {
-    /// New width of the image
-    #[clap(short, long)]
-    width: u32,
+    /// New width of the image. Derived from the height and the aspect ratio
+    /// when not set
+    #[clap(short, long, required_unless_present = "height")]
+    width: Option<u32>,
 
-    /// Whether the width is a percentage
+    /// Whether the width is a percentage. Values above 100 upscale
     #[clap(long)]
     width_as_percentage: bool,
 
-    /// New height of the image
+    /// New height of the image. Derived from the width and the aspect ratio
+    /// when not set
     #[clap(short('H'), long)]
-    height: u32,
+    height: Option<u32>,
 
-    /// Whether the height is a percentage
+    /// Whether the height is a percentage. Values above 100 upscale
     #[clap(long)]
     height_as_percentage: bool,
 
-    /// Whether to keep the aspect ratio
-    #[clap(short('r'), long)]
+    /// Whether to keep the aspect ratio. Same as --mode fit
+    #[clap(short('r'), long, conflicts_with = "mode")]
     keep_ratio: bool,
 
-    /// Output path
+    /// How to fit the image into the size when both dimensions are given.
+    /// Defaults to stretch
+    #[clap(short, long)]
+    mode: Option<ResizeMode>,
+
+    /// Color of the padding in pad mode. Format: (r, g, b, a)
+    #[clap(short('c'), long)]
+    fill_color: Option<String>,
+
+    /// Resampling filter
+    #[clap(short, long, default_value = "lanczos3")]
+    filter: ResizeFilter,
+
+    /// Output path. A directory when the input is a directory or a glob
     #[clap(short, long)]
     output: String,
+
+    #[clap(flatten)]
+    output_options: output::OutputOptions,
+
+    #[clap(flatten)]
+    batch: batch::BatchOptions,
+}
+
+/// Resampling filter, from the fastest to the sharpest. Nearest keeps hard
+/// pixel edges, which suits pixel art but aliases photos.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum ResizeFilter {
+    Nearest,
+    Triangle,
+    CatmullRom,
+    Gaussian,
+    Lanczos3,
+}
+
+impl ResizeFilter {
+    pub fn to_filter_type(self) -> imageops::FilterType {
+        return match self {
+            ResizeFilter::Nearest => imageops::FilterType::Nearest,
+            ResizeFilter::Triangle => imageops::FilterType::Triangle,
+            ResizeFilter::CatmullRom => imageops::FilterType::CatmullRom,
+            ResizeFilter::Gaussian => imageops::FilterType::Gaussian,
+            ResizeFilter::Lanczos3 => imageops::FilterType::Lanczos3,
+        };
+    }
 }
 
 #[derive(Debug, Error)]
 pub enum ResizeError {
     #[error("Image crate error")]
     ImageCrateError(image::ImageError),
+    #[error("Either a width or a height is required")]
+    MissingSizeError,
+    #[error("Parse error")]
+    ParseError,
+    #[error("{0}")]
+    BatchError(batch::BatchError),
 }
 
 impl ResizeCommand {
     pub fn execute(&self, input: &str) -> Result<(), ResizeError> {
+        if batch::is_batch_input(input) {
+            return batch::execute(self, input, &self.output, &self.batch)
+                .map_err(|e| ResizeError::BatchError(e));
+        }
+
         let input_path = utils::to_absolute_path(input);
         let output_path = utils::to_absolute_path(&self.output);
 
-        let img = image::open(input_path).map_err(|e| ResizeError::ImageCrateError(e))?;
+        self.process(&input_path, &output_path)?;
 
-        let new_height = self.get_new_height(&img);
-        let new_width = self.get_new_width(&img);
+        println!("Image saved to {}", output_path.display());
 
-        let resized = if self.keep_ratio {
-            img.resize(new_width, new_height, image::imageops::FilterType::Nearest)
-        } else {
-            img.resize_exact(new_width, new_height, image::imageops::FilterType::Nearest)
+        return Ok(());
+    }
+}
+
+impl BatchOperation for ResizeCommand {
+    type Error = ResizeError;
+
+    fn process(
+        &self,
+        input_path: &path::Path,
+        output_path: &path::Path,
+    ) -> Result<(), ResizeError> {
+        let (img, metadata) =
+            metadata::open(input_path).map_err(|e| ResizeError::ImageCrateError(e))?;
+
+        let (width, height) = img.dimensions();
+
+        let mode = match self.mode {
+            Some(mode) => mode,
+            None if self.keep_ratio => ResizeMode::Fit,
+            None => ResizeMode::Stretch,
         };
 
-        resized
-            .save(&output_path)
-            .map_err(|e| ResizeError::ImageCrateError(e))?;
+        let plan = resize_plan::get_resize_plan(
+            (width, height),
+            resize_plan::get_dimension(self.width, self.width_as_percentage, width),
+            resize_plan::get_dimension(self.height, self.height_as_percentage, height),
+            mode,
+        )
+        .ok_or(ResizeError::MissingSizeError)?;
+
+        let fill_color = match &self.fill_color {
+            Some(color) => {
+                image_utils::from_str_to_rgba(color).map_err(|_e| ResizeError::ParseError)?
+            }
+            None => Rgba::from([0u8, 0u8, 0u8, 0u8]),
+        };
 
-        println!("Image saved to {}", output_path.display());
+        let resized = resize_to_plan(&img, &plan, mode, self.filter, fill_color);
+
+        output::save(&resized, output_path, &metadata, &self.output_options)
+            .map_err(|e| ResizeError::ImageCrateError(e))?;
 
         return Ok(());
     }
+}
 
-    fn get_new_height(&self, img: &image::DynamicImage) -> u32 {
-        if self.height_as_percentage {
-            let height = img.height();
-            return (height as f32 * self.height.clamp(0, 100) as f32 / 100.0) as u32;
-        }
-
-        return self.height;
+/// Resizes an image, either to the exact dimensions or to fit inside them
+/// while keeping its aspect ratio
+pub fn resize(
+    img: &image::DynamicImage,
+    width: u32,
+    height: u32,
+    keep_ratio: bool,
+    filter: ResizeFilter,
+) -> image::DynamicImage {
+    if keep_ratio {
+        return img.resize(width, height, filter.to_filter_type());
     }
 
-    fn get_new_width(&self, img: &image::DynamicImage) -> u32 {
-        if self.width_as_percentage {
-            let width = img.width();
-            return (width as f32 * self.width.clamp(0, 100) as f32 / 100.0) as u32;
-        }
+    return img.resize_exact(width, height, filter.to_filter_type());
+}
 
-        return self.width;
-    }
+/// Scales an image as planned, then crops the overflow in fill mode or pads
+/// it with a color in pad mode, keeping the content centered
+pub fn resize_to_plan(
+    img: &DynamicImage,
+    plan: &ResizePlan,
+    mode: ResizeMode,
+    filter: ResizeFilter,
+    fill_color: Rgba<u8>,
+) -> DynamicImage {
+    let (scaled_width, scaled_height) = plan.scaled;
+    let (canvas_width, canvas_height) = plan.canvas;
+
+    let scaled = resize(img, scaled_width, scaled_height, false, filter);
+
+    return match mode {
+        ResizeMode::Fill => scaled.crop_imm(
+            (scaled_width - canvas_width) / 2,
+            (scaled_height - canvas_height) / 2,
+            canvas_width,
+            canvas_height,
+        ),
+        ResizeMode::Pad => {
+            let mut canvas = RgbaImage::from_pixel(canvas_width, canvas_height, fill_color);
+
+            imageops::overlay(
+                &mut canvas,
+                &scaled.to_rgba8(),
+                ((canvas_width - scaled_width) / 2) as i64,
+                ((canvas_height - scaled_height) / 2) as i64,
+            );
+
+            let padded = DynamicImage::ImageRgba8(canvas);
+
+            // Formats without transparency, such as JPEG, can not save RGBA
+            if img.color().has_alpha() {
+                padded
+            } else {
+                DynamicImage::ImageRgb8(padded.to_rgb8())
+            }
+        }
+        ResizeMode::Stretch | ResizeMode::Fit => scaled,
+    };
 }
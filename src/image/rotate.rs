@@ -1,9 +1,13 @@
+use std::path;
+
 use clap::Args;
-use image::{ImageBuffer, Rgba};
+use image::{DynamicImage, ImageBuffer, Rgba};
 use imageproc::geometric_transformations;
 
 use thiserror::Error;
 
+use super::batch::{self, BatchOperation};
+use super::{metadata, output};
 use crate::image::utils as image_utils;
 use crate::internal::utils;
 
@@ -21,9 +25,15 @@ pub struct RotateCommand {
     #[clap(short, long)]
     fill_color: Option<String>,
 
-    /// Output path
+    /// Output path. A directory when the input is a directory or a glob
     #[clap(short, long)]
     output: String,
+
+    #[clap(flatten)]
+    output_options: output::OutputOptions,
+
+    #[clap(flatten)]
+    batch: batch::BatchOptions,
 }
 
 #[derive(Debug, Error)]
@@ -32,50 +42,38 @@ pub enum RotateError {
     ImageCrateError(image::ImageError),
     #[error("Parse error")]
     ParseError,
+    #[error("{0}")]
+    BatchError(batch::BatchError),
 }
 
 impl RotateCommand {
     pub fn execute(&self, input: &str) -> Result<(), RotateError> {
+        if batch::is_batch_input(input) {
+            return batch::execute(self, input, &self.output, &self.batch)
+                .map_err(|e| RotateError::BatchError(e));
+        }
+
         let input_path = utils::to_absolute_path(input);
         let output_path = utils::to_absolute_path(&self.output);
 
-        let img = image::open(input_path).map_err(|e| RotateError::ImageCrateError(e))?;
-
-        let height = img.height();
-        let width = img.width();
+        self.process(&input_path, &output_path)?;
 
-        let (center_x, center_y) = image_utils::get_image_center(&img.to_rgba8());
-
-        let new_width = if self.perserve_size {
-            width
-        } else {
-            (width as f32 * self.angle.to_radians().cos()
-                + height as f32 * self.angle.to_radians().sin())
-            .round()
-            .abs() as u32
-        };
-
-        let new_height = if self.perserve_size {
-            height
-        } else {
-            (width as f32 * self.angle.to_radians().sin()
-                + height as f32 * self.angle.to_radians().cos())
-            .round()
-            .abs() as u32
-        };
+        println!("Image saved to {}", output_path.display());
 
-        let mut buffer = ImageBuffer::from_pixel(new_width, new_height, Rgba([0u8, 0u8, 0u8, 0u8]));
+        return Ok(());
+    }
+}
 
-        let (new_center_x, new_center_y) = image_utils::get_image_center(&buffer);
+impl BatchOperation for RotateCommand {
+    type Error = RotateError;
 
-        let projection = geometric_transformations::Projection::translate(-center_x, -center_y)
-            .and_then(geometric_transformations::Projection::rotate(
-                self.angle.to_radians(),
-            ))
-            .and_then(geometric_transformations::Projection::translate(
-                new_center_x,
-                new_center_y,
-            ));
+    fn process(
+        &self,
+        input_path: &path::Path,
+        output_path: &path::Path,
+    ) -> Result<(), RotateError> {
+        let (img, metadata) =
+            metadata::open(input_path).map_err(|e| RotateError::ImageCrateError(e))?;
 
         let fill_color = match &self.fill_color {
             Some(color) => {
@@ -84,20 +82,64 @@ impl RotateCommand {
             None => Rgba::from([0u8, 0u8, 0u8, 0u8]),
         };
 
-        geometric_transformations::warp_into(
-            &img.to_rgba8(),
-            &projection,
-            geometric_transformations::Interpolation::Nearest,
-            fill_color,
-            &mut buffer,
-        );
+        let rotated = rotate(&img, self.angle, self.perserve_size, fill_color);
 
-        buffer
-            .save(&output_path)
+        output::save(&rotated, output_path, &metadata, &self.output_options)
             .map_err(|e| RotateError::ImageCrateError(e))?;
 
-        println!("Image saved to {}", output_path.display());
-
         return Ok(());
     }
 }
+
+/// Rotates an image clockwise around its center, filling the uncovered area
+/// with the given color
+pub fn rotate(
+    img: &DynamicImage,
+    angle: f32,
+    perserve_size: bool,
+    fill_color: Rgba<u8>,
+) -> DynamicImage {
+    let height = img.height();
+    let width = img.width();
+
+    let (center_x, center_y) = image_utils::get_image_center(&img.to_rgba8());
+
+    let new_width = if perserve_size {
+        width
+    } else {
+        (width as f32 * angle.to_radians().cos().abs()
+            + height as f32 * angle.to_radians().sin().abs())
+        .round() as u32
+    };
+
+    let new_height = if perserve_size {
+        height
+    } else {
+        (width as f32 * angle.to_radians().sin().abs()
+            + height as f32 * angle.to_radians().cos().abs())
+        .round() as u32
+    };
+
+    let mut buffer = ImageBuffer::from_pixel(new_width, new_height, Rgba([0u8, 0u8, 0u8, 0u8]));
+
+    let (new_center_x, new_center_y) = image_utils::get_image_center(&buffer);
+
+    let projection = geometric_transformations::Projection::translate(-center_x, -center_y)
+        .and_then(geometric_transformations::Projection::rotate(
+            angle.to_radians(),
+        ))
+        .and_then(geometric_transformations::Projection::translate(
+            new_center_x,
+            new_center_y,
+        ));
+
+    geometric_transformations::warp_into(
+        &img.to_rgba8(),
+        &projection,
+        geometric_transformations::Interpolation::Nearest,
+        fill_color,
+        &mut buffer,
+    );
+
+    return DynamicImage::ImageRgba8(buffer);
+}
@@ -0,0 +1,173 @@
+use std::path;
+
+use clap::{Args, ValueEnum};
+use image::DynamicImage;
+use imageproc::filter;
+use thiserror::Error;
+
+use super::batch::{self, BatchOperation};
+use super::utils as image_utils;
+use super::{metadata, output};
+use crate::internal::utils;
+
+#[derive(Args)]
+pub struct SharpenCommand {
+    /// How the image is sharpened
+    #[clap(short, long, default_value = "unsharp-mask")]
+    method: SharpenMethod,
+
+    /// Size of the details the unsharp mask enhances, as the standard
+    /// deviation of its blur in pixels. Note: value will be clamped between
+    /// 0.1 and 100
+    #[clap(short, long, default_value = "1.0")]
+    radius: f32,
+
+    /// Strength of the sharpening in percent
+    #[clap(short, long, default_value = "100")]
+    amount: f32,
+
+    /// Smallest difference from the blurred image, between 0 and 255, that
+    /// the unsharp mask sharpens. Higher values keep smooth areas and noise
+    /// untouched
+    #[clap(short, long, default_value = "0")]
+    threshold: u8,
+
+    /// Output path. A directory when the input is a directory or a glob
+    #[clap(short, long)]
+    output: String,
+
+    #[clap(flatten)]
+    output_options: output::OutputOptions,
+
+    #[clap(flatten)]
+    batch: batch::BatchOptions,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum SharpenMethod {
+    /// Adds the difference between the image and a blurred copy of it
+    UnsharpMask,
+    /// Fixed 3x3 sharpening kernel, quick but only affects fine details
+    Convolution,
+}
+
+#[derive(Debug, Error)]
+pub enum SharpenError {
+    #[error("Image crate error")]
+    ImageCrateError(image::ImageError),
+    #[error("{0}")]
+    BatchError(batch::BatchError),
+}
+
+impl SharpenCommand {
+    pub fn execute(&self, input: &str) -> Result<(), SharpenError> {
+        if batch::is_batch_input(input) {
+            return batch::execute(self, input, &self.output, &self.batch)
+                .map_err(|e| SharpenError::BatchError(e));
+        }
+
+        let input_path = utils::to_absolute_path(input);
+        let output_path = utils::to_absolute_path(&self.output);
+
+        self.process(&input_path, &output_path)?;
+
+        println!("Image saved to {}", output_path.display());
+
+        return Ok(());
+    }
+}
+
+impl BatchOperation for SharpenCommand {
+    type Error = SharpenError;
+
+    fn process(
+        &self,
+        input_path: &path::Path,
+        output_path: &path::Path,
+    ) -> Result<(), SharpenError> {
+        let (img, metadata) =
+            metadata::open(input_path).map_err(|e| SharpenError::ImageCrateError(e))?;
+
+        let sharpened = match self.method {
+            SharpenMethod::UnsharpMask => {
+                unsharp_mask(&img, self.radius, self.amount, self.threshold)
+            }
+            SharpenMethod::Convolution => sharpen(&img, self.amount),
+        };
+
+        output::save(&sharpened, output_path, &metadata, &self.output_options)
+            .map_err(|e| SharpenError::ImageCrateError(e))?;
+
+        return Ok(());
+    }
+}
+
+/// Sharpens an image by adding the amount in percent of its difference with
+/// a gaussian blurred copy. Channels that differ less than the threshold
+/// from the blurred copy are left as they are.
+pub fn unsharp_mask(img: &DynamicImage, radius: f32, amount: f32, threshold: u8) -> DynamicImage {
+    let mut pixels = img.to_rgba32f();
+
+    // Blurring premultiplied colors keeps transparent pixels, whatever their
+    // color, from bleeding into their neighbours as dark halos
+    let mut premultiplied = pixels.clone();
+
+    for pixel in premultiplied.pixels_mut() {
+        for channel in 0..3 {
+            pixel[channel] *= pixel[3];
+        }
+    }
+
+    let mut blurred = filter::gaussian_blur_f32(&premultiplied, radius.clamp(0.1, 100.0));
+
+    for pixel in blurred.pixels_mut() {
+        if pixel[3] > 0.0 {
+            for channel in 0..3 {
+                pixel[channel] /= pixel[3];
+            }
+        }
+    }
+
+    let amount = amount.max(0.0) / 100.0;
+    let threshold = threshold as f32 / 255.0;
+
+    for (pixel, blurred_pixel) in pixels.pixels_mut().zip(blurred.pixels()) {
+        for channel in 0..3 {
+            let difference = pixel[channel] - blurred_pixel[channel];
+
+            if difference.abs() >= threshold {
+                pixel[channel] = (pixel[channel] + difference * amount).clamp(0.0, 1.0);
+            }
+        }
+    }
+
+    return image_utils::to_color_type(DynamicImage::ImageRgba32F(pixels), img.color());
+}
+
+/// Sharpens an image with a 3x3 kernel that subtracts the direct neighbours
+/// of each pixel, weighted by the amount in percent
+pub fn sharpen(img: &DynamicImage, amount: f32) -> DynamicImage {
+    let strength = amount.max(0.0) / 100.0;
+    let kernel = [
+        0.0,
+        -strength,
+        0.0,
+        -strength,
+        1.0 + 4.0 * strength,
+        -strength,
+        0.0,
+        -strength,
+        0.0,
+    ];
+
+    let mut pixels = img.to_rgba32f();
+    let filtered = filter::filter3x3::<_, f32, f32>(&pixels, &kernel);
+
+    for (pixel, filtered_pixel) in pixels.pixels_mut().zip(filtered.pixels()) {
+        for channel in 0..3 {
+            pixel[channel] = filtered_pixel[channel].clamp(0.0, 1.0);
+        }
+    }
+
+    return image_utils::to_color_type(DynamicImage::ImageRgba32F(pixels), img.color());
+}
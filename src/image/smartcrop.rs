@@ -0,0 +1,343 @@
+use std::path;
+
+use clap::Args;
+use image::{imageops, DynamicImage, GenericImageView};
+use imageproc::gradients;
+use log::debug;
+use thiserror::Error;
+
+use super::batch::{self, BatchOperation};
+use super::resize;
+use super::{metadata, output};
+use crate::internal::utils;
+
+/// Longest side of the copy the image is analyzed on
+const ANALYSIS_SIZE: u32 = 256;
+
+const EDGE_WEIGHT: f32 = 1.0;
+const SKIN_WEIGHT: f32 = 1.5;
+const SATURATION_WEIGHT: f32 = 0.3;
+
+/// Normalized rgb direction of a typical skin tone
+const SKIN_COLOR: [f32; 3] = [0.78, 0.57, 0.44];
+const SKIN_THRESHOLD: f32 = 0.8;
+const SATURATION_THRESHOLD: f32 = 0.4;
+
+#[derive(Args)]
+pub struct SmartCropCommand {
+    /// Width of the resulting image
+    #[clap(short, long)]
+    width: u32,
+
+    /// Height of the resulting image
+    #[clap(short('H'), long)]
+    height: u32,
+
+    /// Smallest crop to consider, relative to the largest one that fits the
+    /// aspect ratio. Lower values allow tighter crops around the subject.
+    /// Note: value will be clamped between 0.1 and 1.0
+    #[clap(short, long, default_value = "1.0")]
+    min_scale: f32,
+
+    /// Resampling filter used to scale the crop to the target size
+    #[clap(short, long, default_value = "lanczos3")]
+    filter: resize::ResizeFilter,
+
+    /// Output path. A directory when the input is a directory or a glob
+    #[clap(short, long)]
+    output: String,
+
+    #[clap(flatten)]
+    output_options: output::OutputOptions,
+
+    #[clap(flatten)]
+    batch: batch::BatchOptions,
+}
+
+#[derive(Debug, Error)]
+pub enum SmartCropError {
+    #[error("{0}")]
+    ImageCrateError(image::ImageError),
+    #[error("Target size must not be empty")]
+    EmptySizeError,
+    #[error("{0}")]
+    BatchError(batch::BatchError),
+}
+
+/// Area of the image, in pixels of the original image
+struct Window {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+impl SmartCropCommand {
+    pub fn execute(&self, input: &str) -> Result<(), SmartCropError> {
+        if self.width == 0 || self.height == 0 {
+            return Err(SmartCropError::EmptySizeError);
+        }
+
+        if batch::is_batch_input(input) {
+            return batch::execute(self, input, &self.output, &self.batch)
+                .map_err(|e| SmartCropError::BatchError(e));
+        }
+
+        let input_path = utils::to_absolute_path(input);
+        let output_path = utils::to_absolute_path(&self.output);
+
+        self.process(&input_path, &output_path)?;
+
+        println!("Image saved to {}", output_path.display());
+
+        return Ok(());
+    }
+}
+
+impl BatchOperation for SmartCropCommand {
+    type Error = SmartCropError;
+
+    fn process(
+        &self,
+        input_path: &path::Path,
+        output_path: &path::Path,
+    ) -> Result<(), SmartCropError> {
+        let (img, metadata) =
+            metadata::open(input_path).map_err(|e| SmartCropError::ImageCrateError(e))?;
+
+        let window = find_best_window(
+            &img,
+            self.width as f32 / self.height as f32,
+            self.min_scale.clamp(0.1, 1.0),
+        );
+
+        debug!(
+            "Best crop: {}x{} at {},{}",
+            window.width, window.height, window.x, window.y
+        );
+
+        let cropped = img.crop_imm(window.x, window.y, window.width, window.height);
+
+        let resized = resize::resize(&cropped, self.width, self.height, false, self.filter);
+
+        output::save(&resized, output_path, &metadata, &self.output_options)
+            .map_err(|e| SmartCropError::ImageCrateError(e))?;
+
+        return Ok(());
+    }
+}
+
+/// Slides windows of the given aspect ratio over the image, at sizes from
+/// the largest that fits down to `min_scale` of it, and returns the one with
+/// the most interest above the average of the image. Windows gain from the
+/// interesting pixels they keep and lose from the dull ones, so the size
+/// follows the subject instead of always being the smallest.
+fn find_best_window(img: &DynamicImage, aspect_ratio: f32, min_scale: f32) -> Window {
+    let (image_width, image_height) = img.dimensions();
+
+    let analysis_scale = (ANALYSIS_SIZE as f32 / image_width.max(image_height) as f32).min(1.0);
+    let analysis = img.resize_exact(
+        ((image_width as f32 * analysis_scale).round() as u32).max(1),
+        ((image_height as f32 * analysis_scale).round() as u32).max(1),
+        imageops::FilterType::Triangle,
+    );
+
+    let (width, height) = analysis.dimensions();
+    let scores = get_score_table(&analysis);
+
+    let image_area = (width * height) as f64;
+    let mean_score = scores.sum(0, 0, width, height) / image_area;
+
+    let mut best = (f64::MIN, 0, 0, 1.0);
+    let mut scale = 1.0;
+
+    while scale >= min_scale - f32::EPSILON {
+        let (window_width, window_height) = get_window_size(width, height, aspect_ratio, scale);
+        let area = (window_width * window_height) as f64;
+
+        for y in 0..=(height - window_height) {
+            for x in 0..=(width - window_width) {
+                let score = (scores.sum(x, y, window_width, window_height) - mean_score * area)
+                    / image_area;
+
+                if score > best.0 {
+                    best = (score, x, y, scale);
+                }
+            }
+        }
+
+        scale -= 0.1;
+    }
+
+    let (_, x, y, scale) = best;
+
+    // The size is computed again on the original image so the aspect ratio
+    // does not suffer from the rounding of the analysis copy
+    let (window_width, window_height) =
+        get_window_size(image_width, image_height, aspect_ratio, scale);
+
+    return Window {
+        x: ((x as f32 / analysis_scale).round() as u32).min(image_width - window_width),
+        y: ((y as f32 / analysis_scale).round() as u32).min(image_height - window_height),
+        width: window_width,
+        height: window_height,
+    };
+}
+
+/// Size of the largest window of an aspect ratio that fits in an image,
+/// multiplied by a scale
+fn get_window_size(width: u32, height: u32, aspect_ratio: f32, scale: f32) -> (u32, u32) {
+    let (full_width, full_height) = if width as f32 / height as f32 > aspect_ratio {
+        (height as f32 * aspect_ratio, height as f32)
+    } else {
+        (width as f32, width as f32 / aspect_ratio)
+    };
+
+    return (
+        ((full_width * scale).round() as u32).clamp(1, width),
+        ((full_height * scale).round() as u32).clamp(1, height),
+    );
+}
+
+/// Summed-area table of the interest of every pixel
+struct ScoreTable {
+    width: usize,
+    sums: Vec<f64>,
+}
+
+impl ScoreTable {
+    fn sum(&self, x: u32, y: u32, width: u32, height: u32) -> f64 {
+        let stride = self.width + 1;
+        let (x0, y0) = (x as usize, y as usize);
+        let (x1, y1) = (x0 + width as usize, y0 + height as usize);
+
+        return self.sums[y1 * stride + x1]
+            - self.sums[y0 * stride + x1]
+            - self.sums[y1 * stride + x0]
+            + self.sums[y0 * stride + x0];
+    }
+}
+
+/// Scores every pixel by how likely it is to be part of the subject, mixing
+/// edge density, skin tones and saturation
+fn get_score_table(img: &DynamicImage) -> ScoreTable {
+    let (width, height) = img.dimensions();
+    let rgb = img.to_rgb8();
+    let edges = gradients::sobel_gradients(&img.to_luma8());
+
+    let max_edge = edges.pixels().map(|p| p.0[0]).max().unwrap_or(0).max(1) as f32;
+
+    let stride = width as usize + 1;
+    let mut sums = vec![0f64; stride * (height as usize + 1)];
+
+    for y in 0..height {
+        let mut row_sum = 0f64;
+
+        for x in 0..width {
+            let [r, g, b] = rgb.get_pixel(x, y).0;
+            let (r, g, b) = (r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0);
+
+            let edge = edges.get_pixel(x, y).0[0] as f32 / max_edge;
+            let score = EDGE_WEIGHT * edge
+                + SKIN_WEIGHT * get_skin_score(r, g, b)
+                + SATURATION_WEIGHT * get_saturation_score(r, g, b);
+
+            row_sum += score as f64;
+
+            let index = (y as usize + 1) * stride + x as usize + 1;
+            sums[index] = sums[index - stride] + row_sum;
+        }
+    }
+
+    return ScoreTable {
+        width: width as usize,
+        sums,
+    };
+}
+
+/// How close a color is to a skin tone, ignoring its brightness, between 0
+/// and 1
+fn get_skin_score(r: f32, g: f32, b: f32) -> f32 {
+    let length = (r * r + g * g + b * b).sqrt();
+    let lightness = (r.max(g).max(b) + r.min(g).min(b)) / 2.0;
+
+    if length == 0.0 || !(0.2..=0.9).contains(&lightness) {
+        return 0.0;
+    }
+
+    let distance = ((r / length - SKIN_COLOR[0]).powi(2)
+        + (g / length - SKIN_COLOR[1]).powi(2)
+        + (b / length - SKIN_COLOR[2]).powi(2))
+    .sqrt();
+
+    let similarity = 1.0 - distance;
+
+    if similarity < SKIN_THRESHOLD {
+        return 0.0;
+    }
+
+    return (similarity - SKIN_THRESHOLD) / (1.0 - SKIN_THRESHOLD);
+}
+
+/// Saturation above a threshold, between 0 and 1, as vivid areas tend to
+/// draw the eye
+fn get_saturation_score(r: f32, g: f32, b: f32) -> f32 {
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let lightness = (max + min) / 2.0;
+
+    if max == min {
+        return 0.0;
+    }
+
+    let saturation = if lightness > 0.5 {
+        (max - min) / (2.0 - max - min)
+    } else {
+        (max - min) / (max + min)
+    };
+
+    if saturation < SATURATION_THRESHOLD {
+        return 0.0;
+    }
+
+    return (saturation - SATURATION_THRESHOLD) / (1.0 - SATURATION_THRESHOLD);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+
+    /// Gray image with a saturated red square as its subject
+    fn image_with_subject(x: u32, y: u32, size: u32) -> DynamicImage {
+        let mut img = RgbImage::from_pixel(200, 100, Rgb([128, 128, 128]));
+
+        for subject_y in y..y + size {
+            for subject_x in x..x + size {
+                img.put_pixel(subject_x, subject_y, Rgb([220, 20, 20]));
+            }
+        }
+
+        return DynamicImage::ImageRgb8(img);
+    }
+
+    #[test]
+    fn large_subject_keeps_a_large_window() {
+        let window = find_best_window(&image_with_subject(55, 5, 90), 1.0, 0.5);
+
+        assert_eq!((window.width, window.height), (100, 100));
+        assert!((45..=55).contains(&window.x), "window at {}", window.x);
+    }
+
+    #[test]
+    fn small_subject_gets_a_tight_window() {
+        let window = find_best_window(&image_with_subject(140, 40, 20), 1.0, 0.5);
+
+        assert_eq!((window.width, window.height), (50, 50));
+        assert!(
+            window.x <= 140 && window.x + 50 >= 160,
+            "window at {}",
+            window.x
+        );
+    }
+}
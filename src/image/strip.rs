@@ -0,0 +1,101 @@
+use std::{fs, path};
+
+use clap::Args;
+use image::ImageFormat;
+use img_parts::{Bytes, DynImage};
+use log::debug;
+use thiserror::Error;
+
+use super::batch::{self, BatchOperation};
+use super::{metadata, output};
+use crate::internal::utils;
+
+#[derive(Args)]
+pub struct StripCommand {
+    /// Also remove the ICC color profile. Colors may look different in
+    /// applications that use it
+    #[clap(long)]
+    remove_icc: bool,
+
+    /// Output path. A directory when the input is a directory or a glob
+    #[clap(short, long)]
+    output: String,
+
+    #[clap(flatten)]
+    output_options: output::OutputOptions,
+
+    #[clap(flatten)]
+    batch: batch::BatchOptions,
+}
+
+#[derive(Debug, Error)]
+pub enum StripError {
+    #[error("{0}")]
+    IoError(std::io::Error),
+    #[error("{0}")]
+    ImageCrateError(image::ImageError),
+    #[error("{0}")]
+    BatchError(batch::BatchError),
+}
+
+impl StripCommand {
+    pub fn execute(&self, input: &str) -> Result<(), StripError> {
+        if batch::is_batch_input(input) {
+            return batch::execute(self, input, &self.output, &self.batch)
+                .map_err(|e| StripError::BatchError(e));
+        }
+
+        let input_path = utils::to_absolute_path(input);
+        let output_path = utils::to_absolute_path(&self.output);
+
+        self.process(&input_path, &output_path)?;
+
+        println!("Image saved to {}", output_path.display());
+
+        return Ok(());
+    }
+}
+
+impl BatchOperation for StripCommand {
+    type Error = StripError;
+
+    fn process(&self, input_path: &path::Path, output_path: &path::Path) -> Result<(), StripError> {
+        let bytes = fs::read(input_path).map_err(|e| StripError::IoError(e))?;
+
+        let same_format = match (
+            ImageFormat::from_path(input_path),
+            ImageFormat::from_path(output_path),
+        ) {
+            (Ok(input_format), Ok(output_format)) => input_format == output_format,
+            _ => false,
+        };
+
+        // JPEG, PNG and WebP images are rewritten without decoding them, so
+        // the pixels are left untouched
+        if same_format {
+            if let Ok(Some(mut container)) = DynImage::from_bytes(Bytes::from(bytes)) {
+                debug!("Stripping metadata without re-encoding");
+
+                metadata::strip(&mut container, self.remove_icc);
+
+                fs::write(output_path, container.encoder().bytes())
+                    .map_err(|e| StripError::IoError(e))?;
+
+                return Ok(());
+            }
+        }
+
+        let (img, metadata) =
+            metadata::open(input_path).map_err(|e| StripError::ImageCrateError(e))?;
+
+        let kept = metadata::Metadata {
+            icc: if self.remove_icc { None } else { metadata.icc },
+            ..Default::default()
+        };
+
+        output::save(&img, output_path, &kept, &self.output_options)
+            .map_err(|e| StripError::ImageCrateError(e))?;
+
+        return Ok(());
+    }
+}
@@ -0,0 +1,178 @@
+use std::{fs, io};
+
+use clap::Args;
+use image::{GrayImage, Luma, Rgba, RgbaImage};
+use imageproc::drawing;
+use rusttype::{Font, Scale};
+
+use super::composite::{composite_pixel, BlendMode};
+
+#[derive(Args)]
+pub struct TextOptions {
+    /// Text to use as overlay instead of a picture. Lines are split on "\n"
+    #[clap(short, long, requires = "font")]
+    pub text: Option<String>,
+
+    /// Path to the TTF or OTF font to draw the text with
+    #[clap(long)]
+    pub font: Option<String>,
+
+    /// Height of the text in pixels. Ignored when the overlay is scaled
+    #[clap(long, default_value = "48")]
+    pub font_size: f32,
+
+    /// Color of the text. Format: (r, g, b, a)
+    #[clap(long, default_value = "(255, 255, 255, 255)")]
+    pub color: String,
+
+    /// Width of the outline around the text in pixels
+    #[clap(long, default_value = "0")]
+    pub stroke: u32,
+
+    /// Color of the outline. Format: (r, g, b, a)
+    #[clap(long, default_value = "(0, 0, 0, 255)")]
+    pub stroke_color: String,
+
+    /// Distance in pixels of the drop shadow below and right of the text
+    #[clap(long, default_value = "0")]
+    pub shadow: u32,
+
+    /// Color of the drop shadow. Format: (r, g, b, a)
+    #[clap(long, default_value = "(0, 0, 0, 128)")]
+    pub shadow_color: String,
+}
+
+pub struct TextStyle {
+    pub size: f32,
+    pub color: Rgba<u8>,
+    pub stroke: u32,
+    pub stroke_color: Rgba<u8>,
+    pub shadow: u32,
+    pub shadow_color: Rgba<u8>,
+}
+
+pub fn load_font(path: &std::path::Path) -> Result<Font<'static>, io::Error> {
+    let bytes = fs::read(path)?;
+
+    return Font::try_from_vec(bytes).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} is not a TTF or OTF font", path.display()),
+        )
+    });
+}
+
+/// Draws text on a transparent layer just big enough to hold it, with its
+/// outline and shadow
+pub fn render_text(font: &Font, text: &str, style: &TextStyle) -> RgbaImage {
+    let scale = Scale::uniform(style.size);
+    let v_metrics = font.v_metrics(scale);
+    let line_height = (v_metrics.ascent - v_metrics.descent + v_metrics.line_gap).ceil() as i32;
+
+    let text = text.replace("\\n", "\n");
+    let lines: Vec<&str> = text.lines().collect();
+
+    let text_width = lines
+        .iter()
+        .map(|line| drawing::text_size(scale, font, line).0)
+        .max()
+        .unwrap_or(0);
+    let text_height = line_height * lines.len() as i32;
+
+    let margin = style.stroke as i32;
+    let width = (text_width + 2 * margin + style.shadow as i32).max(1) as u32;
+    let height = (text_height + 2 * margin + style.shadow as i32).max(1) as u32;
+
+    let mut fill = GrayImage::new(width, height);
+
+    for (index, line) in lines.iter().enumerate() {
+        drawing::draw_text_mut(
+            &mut fill,
+            Luma([255]),
+            margin,
+            margin + index as i32 * line_height,
+            scale,
+            font,
+            line,
+        );
+    }
+
+    let outline = if style.stroke > 0 {
+        Some(dilate(&fill, style.stroke))
+    } else {
+        None
+    };
+
+    let mut layer = RgbaImage::new(width, height);
+
+    if style.shadow > 0 {
+        let silhouette = outline.as_ref().unwrap_or(&fill);
+
+        for (x, y, coverage) in silhouette.enumerate_pixels() {
+            let (shadow_x, shadow_y) = (x + style.shadow, y + style.shadow);
+
+            if shadow_x < width && shadow_y < height {
+                let pixel = layer.get_pixel_mut(shadow_x, shadow_y);
+                *pixel = composite_pixel(
+                    *pixel,
+                    style.shadow_color,
+                    coverage[0] as f32 / 255.0,
+                    BlendMode::Normal,
+                );
+            }
+        }
+    }
+
+    if let Some(outline) = &outline {
+        paint(&mut layer, outline, style.stroke_color);
+    }
+
+    paint(&mut layer, &fill, style.color);
+
+    return layer;
+}
+
+/// Paints a color through a coverage mask on top of the layer
+fn paint(layer: &mut RgbaImage, mask: &GrayImage, color: Rgba<u8>) {
+    for (pixel, coverage) in layer.pixels_mut().zip(mask.pixels()) {
+        *pixel = composite_pixel(*pixel, color, coverage[0] as f32 / 255.0, BlendMode::Normal);
+    }
+}
+
+/// Grows a coverage mask by a radius, keeping the strongest coverage within a
+/// disk around each pixel
+fn dilate(mask: &GrayImage, radius: u32) -> GrayImage {
+    let (width, height) = mask.dimensions();
+    let radius = radius as i32;
+    let reach = (radius as f32 + 0.5).powi(2);
+
+    let disk: Vec<(i32, i32)> = (-radius..=radius)
+        .flat_map(|dy| (-radius..=radius).map(move |dx| (dx, dy)))
+        .filter(|(dx, dy)| (dx * dx + dy * dy) as f32 <= reach)
+        .collect();
+
+    let mut dilated = GrayImage::new(width, height);
+
+    for (x, y, pixel) in dilated.enumerate_pixels_mut() {
+        let mut strongest = 0;
+
+        for (dx, dy) in disk.iter() {
+            let (source_x, source_y) = (x as i32 + dx, y as i32 + dy);
+
+            if source_x < 0 || source_y < 0 || source_x >= width as i32 || source_y >= height as i32
+            {
+                continue;
+            }
+
+            strongest = strongest.max(mask.get_pixel(source_x as u32, source_y as u32)[0]);
+
+            if strongest == 255 {
+                break;
+            }
+        }
+
+        *pixel = Luma([strongest]);
+    }
+
+    return dilated;
+}
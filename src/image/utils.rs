@@ -1,4 +1,4 @@
-use image::{ImageBuffer, Pixel};
+use image::{ColorType, DynamicImage, ImageBuffer, Pixel};
 
 pub fn get_image_center<P>(img: &ImageBuffer<P, Vec<<P as Pixel>::Subpixel>>) -> (f32, f32)
 where
@@ -27,22 +27,81 @@ pub fn from_str_to_rgba(rgba_string: &str) -> Result<image::Rgba<u8>, std::num::
     return Ok(color);
 }
 
-pub fn blend_with_opacity(
-    below_color: image::Rgba<u8>,
-    overlay_color: image::Rgba<u8>,
-    opacity: f32,
-) -> image::Rgba<u8> {
-    // ( (1-p)R1 + p*R2, (1-p)*G1 + p*G2, (1-p)*B1 + p*B2 )
-    let below_color = below_color.channels();
-    let overlay_color = overlay_color.channels();
+/// Rec. 709 weights of the red, green and blue channels in the luminance
+pub const LUMA_WEIGHTS: [f32; 3] = [0.2126, 0.7152, 0.0722];
 
-    let alpha_below = overlay_color[3] as f32 / 255f32;
+/// Converts an sRGB encoded channel between 0 and 1 to linear light
+pub fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        return value / 12.92;
+    }
 
-    let opacity = opacity.clamp(0f32, 1f32) * alpha_below;
+    return ((value + 0.055) / 1.055).powf(2.4);
+}
+
+/// Converts a linear light channel between 0 and 1 to sRGB encoding
+pub fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        return value * 12.92;
+    }
+
+    return 1.055 * value.powf(1.0 / 2.4) - 0.055;
+}
+
+pub fn luminance(red: f32, green: f32, blue: f32) -> f32 {
+    return LUMA_WEIGHTS[0] * red + LUMA_WEIGHTS[1] * green + LUMA_WEIGHTS[2] * blue;
+}
+
+/// Converts an image back to a color type, so editing colors in floating
+/// point keeps the channels and bit depth of the original
+pub fn to_color_type(img: DynamicImage, color: ColorType) -> DynamicImage {
+    return match color {
+        ColorType::L8 => DynamicImage::ImageLuma8(img.to_luma8()),
+        ColorType::La8 => DynamicImage::ImageLumaA8(img.to_luma_alpha8()),
+        ColorType::Rgb8 => DynamicImage::ImageRgb8(img.to_rgb8()),
+        ColorType::Rgba8 => DynamicImage::ImageRgba8(img.to_rgba8()),
+        ColorType::L16 => DynamicImage::ImageLuma16(img.to_luma16()),
+        ColorType::La16 => DynamicImage::ImageLumaA16(img.to_luma_alpha16()),
+        ColorType::Rgb16 => DynamicImage::ImageRgb16(img.to_rgb16()),
+        ColorType::Rgba16 => DynamicImage::ImageRgba16(img.to_rgba16()),
+        ColorType::Rgb32F => DynamicImage::ImageRgb32F(img.to_rgb32f()),
+        _ => DynamicImage::ImageRgba32F(img.to_rgba32f()),
+    };
+}
+
+/// Size or offset given either in pixels (100) or as a percentage of the
+/// image dimension it applies to (50%)
+#[derive(Clone, Copy, Debug)]
+pub enum Length {
+    Pixels(u32),
+    Percentage(f32),
+}
+
+impl Length {
+    pub fn to_pixels(self, total: u32) -> u32 {
+        return match self {
+            Length::Pixels(pixels) => pixels,
+            Length::Percentage(percentage) => (total as f32 * percentage / 100.0).round() as u32,
+        };
+    }
+}
+
+impl std::str::FromStr for Length {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
 
-    let r = ((1f32 - opacity) * below_color[0] as f32 + opacity * overlay_color[0] as f32) as u8;
-    let g = ((1f32 - opacity) * below_color[1] as f32 + opacity * overlay_color[1] as f32) as u8;
-    let b = ((1f32 - opacity) * below_color[2] as f32 + opacity * overlay_color[2] as f32) as u8;
+        if let Some(percentage) = value.strip_suffix('%') {
+            return match percentage.trim().parse::<f32>() {
+                Ok(percentage) if percentage >= 0.0 => Ok(Length::Percentage(percentage)),
+                _ => Err(format!("Invalid percentage: {}", value)),
+            };
+        }
 
-    return image::Rgba([r, g, b, below_color[3]]);
+        return value
+            .parse()
+            .map(Length::Pixels)
+            .map_err(|_| format!("Expected pixels (100) or a percentage (50%): {}", value));
+    }
 }
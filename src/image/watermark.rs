@@ -1,41 +1,107 @@
+use std::path;
+
 use clap::{Args, ValueEnum};
-use image::GenericImageView;
-use log::error;
+use image::{DynamicImage, GenericImageView, Rgba, RgbaImage};
+use log::{error, warn};
 use thiserror::Error;
 
-use super::utils as image_utils;
+use super::batch::{self, BatchOperation};
+use super::composite::{self, BlendMode};
+use super::resize::ResizeFilter;
+use super::rotate;
+use super::text::{self, TextStyle};
+use super::utils::{self as image_utils, Length};
+use super::{metadata, output};
 use crate::internal::utils;
 
 #[derive(Args)]
 pub struct WatermarkCommand {
     /// Path to picture to use as overlay
-    #[clap(short, long)]
-    watermark: String,
+    #[clap(short, long, required_unless_present = "text", conflicts_with = "text")]
+    watermark: Option<String>,
 
-    /// Position to place the overlay
-    #[clap(short, long, default_value = "center")]
-    position: WatermarkPosition,
+    #[clap(flatten)]
+    text: text::TextOptions,
 
     /// Opacity of the overlay
     #[clap(short('O'), long, default_value = "1.0")]
     opacity: f32,
 
+    /// How the colors of the overlay mix with the image
+    #[clap(short, long, default_value = "normal")]
+    blend: BlendMode,
+
     /// The percentage of width the watermark should take up. Note: value will
-    /// be clamped between 0.0 and 1.0
+    /// be clamped between 0.0 and 1.0. Text is drawn at the matching font size
     #[clap(short, long)]
     scale: Option<f32>,
 
-    /// Path to output file
+    #[clap(flatten)]
+    placement: PlacementOptions,
+
+    /// Resampling filter used when scaling the watermark
+    #[clap(short, long, default_value = "lanczos3")]
+    filter: ResizeFilter,
+
+    /// Output path. A directory when the input is a directory or a glob
     #[clap(short, long)]
     output: String,
+
+    #[clap(flatten)]
+    output_options: output::OutputOptions,
+
+    #[clap(flatten)]
+    batch: batch::BatchOptions,
+}
+
+/// Where the overlay goes relative to its position, and whether it is
+/// repeated across the image. Shared by the image and video watermarks.
+#[derive(Args, Clone)]
+pub struct PlacementOptions {
+    /// Position to place the overlay
+    #[clap(short, long, default_value = "center")]
+    pub position: WatermarkPosition,
+
+    /// Space kept between the overlay and the edges, in pixels (20) or as a
+    /// percentage of the width and height (5%)
+    #[clap(short, long, default_value = "0")]
+    pub margin: Length,
+
+    /// Pixels to move the overlay right from its position. Negative values
+    /// move it left
+    #[clap(short('x'), long, default_value = "0", allow_negative_numbers = true)]
+    pub offset_x: i32,
+
+    /// Pixels to move the overlay down from its position. Negative values
+    /// move it up
+    #[clap(short('y'), long, default_value = "0", allow_negative_numbers = true)]
+    pub offset_y: i32,
+
+    /// Repeat the overlay in a diagonal pattern over the whole picture. The
+    /// position, margin and offsets are ignored
+    #[clap(long)]
+    pub tile: bool,
+
+    /// Space between repeated overlays, in pixels (40) or as a percentage of
+    /// the overlay size (50%)
+    #[clap(long, default_value = "50%")]
+    pub tile_spacing: Length,
+
+    /// Clockwise rotation of repeated overlays in degrees
+    #[clap(long, default_value = "-30", allow_negative_numbers = true)]
+    pub tile_angle: f32,
 }
 
 #[derive(ValueEnum, Clone)]
 pub enum WatermarkPosition {
     TopLeft,
+    Top,
     TopRight,
+    Left,
     Center,
+    Right,
     BottomLeft,
+    Bottom,
     BottomRight,
 }
 
@@ -45,73 +111,303 @@ pub enum WatermarkError {
     CrateImageError(image::ImageError),
     #[error("Dimension error")]
     DimensionError,
+    #[error("{0}")]
+    FontError(std::io::Error),
+    #[error("Parse error")]
+    ParseError,
+    #[error("{0}")]
+    BatchError(batch::BatchError),
+}
+
+impl WatermarkPosition {
+    /// Top left corner that places an item of the given size at this position
+    /// inside a container. Items bigger than the container stick to its top
+    /// left corner.
+    pub fn get_offset(&self, container: (u32, u32), item: (u32, u32)) -> (u32, u32) {
+        let free_width = container.0.saturating_sub(item.0);
+        let free_height = container.1.saturating_sub(item.1);
+
+        let x = match self {
+            WatermarkPosition::TopLeft
+            | WatermarkPosition::Left
+            | WatermarkPosition::BottomLeft => 0,
+            WatermarkPosition::Top | WatermarkPosition::Center | WatermarkPosition::Bottom => {
+                free_width / 2
+            }
+            WatermarkPosition::TopRight
+            | WatermarkPosition::Right
+            | WatermarkPosition::BottomRight => free_width,
+        };
+
+        let y = match self {
+            WatermarkPosition::TopLeft | WatermarkPosition::Top | WatermarkPosition::TopRight => 0,
+            WatermarkPosition::Left | WatermarkPosition::Center | WatermarkPosition::Right => {
+                free_height / 2
+            }
+            WatermarkPosition::BottomLeft
+            | WatermarkPosition::Bottom
+            | WatermarkPosition::BottomRight => free_height,
+        };
+
+        return (x, y);
+    }
+}
+
+impl Default for PlacementOptions {
+    fn default() -> Self {
+        return Self {
+            position: WatermarkPosition::Center,
+            margin: Length::Pixels(0),
+            offset_x: 0,
+            offset_y: 0,
+            tile: false,
+            tile_spacing: Length::Percentage(50.0),
+            tile_angle: -30.0,
+        };
+    }
+}
+
+impl PlacementOptions {
+    /// Size of the area inside the margins
+    fn get_area(&self, container: (u32, u32)) -> (u32, u32) {
+        let width = container
+            .0
+            .saturating_sub(2 * self.margin.to_pixels(container.0));
+        let height = container
+            .1
+            .saturating_sub(2 * self.margin.to_pixels(container.1));
+
+        return (width, height);
+    }
+
+    /// Size an item has to be scaled down to, keeping its aspect ratio, so it
+    /// fits inside the margins. Items that already fit keep their size, and
+    /// None is returned when there is no space left inside the margins.
+    pub fn fit(&self, container: (u32, u32), item: (u32, u32)) -> Option<(u32, u32)> {
+        let (width, height) = if self.tile {
+            container
+        } else {
+            self.get_area(container)
+        };
+
+        if width == 0 || height == 0 || item.0 == 0 || item.1 == 0 {
+            return None;
+        }
+
+        if item.0 <= width && item.1 <= height {
+            return Some(item);
+        }
+
+        let ratio = (width as f32 / item.0 as f32).min(height as f32 / item.1 as f32);
+        let fitted_width = ((item.0 as f32 * ratio).round() as u32).clamp(1, width);
+        let fitted_height = ((item.1 as f32 * ratio).round() as u32).clamp(1, height);
+
+        return Some((fitted_width, fitted_height));
+    }
+
+    /// Top left corner of an item placed at a position inside the margins
+    /// and moved by the offsets, kept inside the container
+    pub fn get_offset(&self, container: (u32, u32), item: (u32, u32)) -> (u32, u32) {
+        let area = self.get_area(container);
+        let (x, y) = self.position.get_offset(area, item);
+
+        let x = (x + self.margin.to_pixels(container.0)) as i64 + self.offset_x as i64;
+        let y = (y + self.margin.to_pixels(container.1)) as i64 + self.offset_y as i64;
+
+        let max_x = container.0.saturating_sub(item.0) as i64;
+        let max_y = container.1.saturating_sub(item.1) as i64;
+
+        return (x.clamp(0, max_x) as u32, y.clamp(0, max_y) as u32);
+    }
+
+    /// Transparent layer of the container size covered with rotated copies
+    /// of the overlay. Every other row is shifted by half a step so the
+    /// copies line up diagonally.
+    pub fn tile(&self, overlay: &DynamicImage, container: (u32, u32)) -> RgbaImage {
+        let tile = rotate::rotate(overlay, self.tile_angle, false, Rgba([0, 0, 0, 0])).to_rgba8();
+
+        let step_x = tile.width() + self.tile_spacing.to_pixels(tile.width());
+        let step_y = tile.height() + self.tile_spacing.to_pixels(tile.height());
+
+        let mut layer = RgbaImage::new(container.0, container.1);
+
+        for (row, y) in (0..container.1 + step_y)
+            .step_by(step_y.max(1) as usize)
+            .enumerate()
+        {
+            let shift = if row % 2 == 1 { step_x / 2 } else { 0 };
+
+            for x in (0..container.0 + step_x).step_by(step_x.max(1) as usize) {
+                image::imageops::overlay(
+                    &mut layer,
+                    &tile,
+                    x as i64 - shift as i64,
+                    y as i64 - (step_y / 2) as i64,
+                );
+            }
+        }
+
+        return layer;
+    }
 }
 
 impl WatermarkCommand {
     pub fn execute(&self, input: &str) -> Result<(), WatermarkError> {
+        if batch::is_batch_input(input) {
+            return batch::execute(self, input, &self.output, &self.batch)
+                .map_err(|e| WatermarkError::BatchError(e));
+        }
+
         let input_path = utils::to_absolute_path(input);
-        let watermark_path = utils::to_absolute_path(&self.watermark);
         let output_path = utils::to_absolute_path(&self.output);
 
-        let mut img = image::open(&input_path).map_err(|e| WatermarkError::CrateImageError(e))?;
-        let (image_width, image_height) = img.dimensions();
+        self.process(&input_path, &output_path)?;
 
-        let mut watermark =
-            image::open(&watermark_path).map_err(|e| WatermarkError::CrateImageError(e))?;
+        println!("Image saved to {}", output_path.display());
 
-        if let Some(scale) = self.scale {
-            let (width, height) = watermark.dimensions();
-            let watermark_ratio = width as f32 / height as f32;
-            let new_width = (image_width as f32 * scale).round() as u32;
-            let new_height = (new_width as f32 / watermark_ratio).round() as u32;
+        return Ok(());
+    }
 
-            watermark =
-                watermark.resize(new_width, new_height, image::imageops::FilterType::Nearest);
-        }
+    /// Draws the text overlay. When scaled, the font size is picked so the
+    /// text takes up the requested part of the image width instead of
+    /// resampling the drawn text.
+    fn render_text(&self, image_width: u32) -> Result<image::DynamicImage, WatermarkError> {
+        let caption = self.text.text.as_deref().unwrap_or_default();
+        let font_path = utils::to_absolute_path(self.text.font.as_deref().unwrap_or_default());
+        let font = text::load_font(&font_path).map_err(|e| WatermarkError::FontError(e))?;
+
+        let parse_color = |color: &str| {
+            return image_utils::from_str_to_rgba(color).map_err(|_e| WatermarkError::ParseError);
+        };
+
+        let mut style = TextStyle {
+            size: self.text.font_size,
+            color: parse_color(&self.text.color)?,
+            stroke: self.text.stroke,
+            stroke_color: parse_color(&self.text.stroke_color)?,
+            shadow: self.text.shadow,
+            shadow_color: parse_color(&self.text.shadow_color)?,
+        };
 
-        let (watermark_width, watermark_height) = watermark.dimensions();
+        let mut layer = text::render_text(&font, caption, &style);
 
-        if watermark_width > image_width || watermark_height > image_height {
-            error!("Watermark is bigger than the image");
-            return Err(WatermarkError::DimensionError);
+        if let Some(scale) = self.scale {
+            let decorations = 2 * style.stroke + style.shadow;
+            let text_width = layer.width().saturating_sub(decorations).max(1);
+            let target_width = (image_width as f32 * scale.clamp(0.0, 1.0)).round() as u32;
+
+            style.size *= target_width.saturating_sub(decorations) as f32 / text_width as f32;
+            layer = text::render_text(&font, caption, &style);
         }
 
-        let (x, y) = match self.position {
-            WatermarkPosition::TopLeft => (0, 0),
-            WatermarkPosition::TopRight => (image_width - watermark_width, 0),
-            WatermarkPosition::BottomLeft => (0, image_height - watermark_height),
-            WatermarkPosition::BottomRight => (
-                image_width - watermark_width,
-                image_height - watermark_height,
-            ),
-            WatermarkPosition::Center => (
-                image_width / 2 - (watermark_width / 2),
-                image_height / 2 - (watermark_height / 2),
-            ),
-        };
+        return Ok(image::DynamicImage::ImageRgba8(layer));
+    }
+}
 
-        let region_of_interest = img.crop(x, y, watermark_width, watermark_height);
-        let mut transparent_watermark = watermark.to_rgba8();
+impl BatchOperation for WatermarkCommand {
+    type Error = WatermarkError;
 
-        for pixel in transparent_watermark
-            .pixels_mut()
-            .zip(region_of_interest.pixels())
-        {
-            let (watermark_pixel, roi_pixel) = pixel;
+    fn process(
+        &self,
+        input_path: &path::Path,
+        output_path: &path::Path,
+    ) -> Result<(), WatermarkError> {
+        let (mut img, metadata) =
+            metadata::open(input_path).map_err(|e| WatermarkError::CrateImageError(e))?;
 
-            let combined_color =
-                image_utils::blend_with_opacity(roi_pixel.2, *watermark_pixel, self.opacity);
+        match &self.watermark {
+            Some(watermark) => {
+                let watermark_path = utils::to_absolute_path(watermark);
+                let (watermark, _) = metadata::open(&watermark_path)
+                    .map_err(|e| WatermarkError::CrateImageError(e))?;
 
-            *watermark_pixel = combined_color;
-        }
+                apply_watermark(
+                    &mut img,
+                    watermark,
+                    &self.placement,
+                    self.opacity,
+                    self.blend,
+                    self.scale,
+                    self.filter,
+                )?;
+            }
+            None => {
+                let caption = self.render_text(img.width())?;
 
-        image::imageops::overlay(&mut img, &transparent_watermark, x as i64, y as i64);
+                apply_watermark(
+                    &mut img,
+                    caption,
+                    &self.placement,
+                    self.opacity,
+                    self.blend,
+                    None,
+                    self.filter,
+                )?;
+            }
+        }
 
-        img.save(&output_path)
+        output::save(&img, output_path, &metadata, &self.output_options)
             .map_err(|e| WatermarkError::CrateImageError(e))?;
 
-        println!("Image saved to {}", output_path.display());
-
         return Ok(());
     }
 }
+
+/// Overlays a watermark on an image at its placement, or tiled over the
+/// whole image, optionally scaled to a proportion of the image width
+/// between 0 and 1. Watermarks that do not fit are scaled down.
+pub fn apply_watermark(
+    img: &mut image::DynamicImage,
+    mut watermark: image::DynamicImage,
+    placement: &PlacementOptions,
+    opacity: f32,
+    blend: BlendMode,
+    scale: Option<f32>,
+    filter: ResizeFilter,
+) -> Result<(), WatermarkError> {
+    let (image_width, image_height) = img.dimensions();
+
+    if let Some(scale) = scale {
+        let (width, height) = watermark.dimensions();
+        let watermark_ratio = width as f32 / height as f32;
+        let new_width = (image_width as f32 * scale.clamp(0.0, 1.0)).round() as u32;
+        let new_height = (new_width as f32 / watermark_ratio).round() as u32;
+
+        watermark = watermark.resize(new_width, new_height, filter.to_filter_type());
+    }
+
+    let (fitted_width, fitted_height) =
+        match placement.fit((image_width, image_height), watermark.dimensions()) {
+            Some(size) => size,
+            None => {
+                error!("No space left for the watermark inside the margins");
+                return Err(WatermarkError::DimensionError);
+            }
+        };
+
+    if (fitted_width, fitted_height) != watermark.dimensions() {
+        warn!(
+            "Watermark is bigger than the image, scaling it down to {}x{}",
+            fitted_width, fitted_height
+        );
+
+        watermark = watermark.resize_exact(fitted_width, fitted_height, filter.to_filter_type());
+    }
+
+    let (x, y, overlay) = if placement.tile {
+        (
+            0,
+            0,
+            placement.tile(&watermark, (image_width, image_height)),
+        )
+    } else {
+        let (x, y) =
+            placement.get_offset((image_width, image_height), (fitted_width, fitted_height));
+
+        (x, y, watermark.to_rgba8())
+    };
+
+    composite::composite(img, &overlay, x, y, opacity, blend);
+
+    return Ok(());
+}
@@ -1 +1,2 @@
+pub mod resize;
 pub mod utils;
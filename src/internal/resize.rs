@@ -0,0 +1,103 @@
+use clap::ValueEnum;
+
+/// How an image or a video is fitted into the requested size when both
+/// dimensions are given
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum ResizeMode {
+    /// Use the exact size, distorting the content if the aspect ratio differs
+    Stretch,
+    /// Shrink or grow to fit inside the size, keeping the aspect ratio
+    Fit,
+    /// Cover the whole size, keeping the aspect ratio, and crop the overflow
+    Fill,
+    /// Fit inside the size and fill the remaining space with a color
+    Pad,
+}
+
+/// Size the content is scaled to, and size of the final frame. They differ
+/// when the content is cropped (fill) or padded (pad) afterwards.
+#[derive(Clone, Copy, Debug)]
+pub struct ResizePlan {
+    pub scaled: (u32, u32),
+    pub canvas: (u32, u32),
+}
+
+/// Resolves a requested dimension, either in pixels or as a percentage of
+/// the original one. Percentages above 100 upscale.
+pub fn get_dimension(value: Option<u32>, as_percentage: bool, original: u32) -> Option<u32> {
+    return value.map(|value| {
+        if as_percentage {
+            return (original as f32 * value as f32 / 100.0).round() as u32;
+        }
+
+        value
+    });
+}
+
+/// Works out the sizes to scale and crop or pad to. When only one dimension
+/// is given the other one follows the aspect ratio and the mode is ignored.
+/// Returns `None` when neither dimension is given.
+pub fn get_resize_plan(
+    original: (u32, u32),
+    width: Option<u32>,
+    height: Option<u32>,
+    mode: ResizeMode,
+) -> Option<ResizePlan> {
+    let (original_width, original_height) = (original.0 as f32, original.1 as f32);
+
+    let (width, height) = match (width, height) {
+        (Some(width), Some(height)) => (width.max(1), height.max(1)),
+        (Some(width), None) => {
+            let height = (width as f32 * original_height / original_width).round() as u32;
+            let size = (width.max(1), height.max(1));
+
+            return Some(ResizePlan {
+                scaled: size,
+                canvas: size,
+            });
+        }
+        (None, Some(height)) => {
+            let width = (height as f32 * original_width / original_height).round() as u32;
+            let size = (width.max(1), height.max(1));
+
+            return Some(ResizePlan {
+                scaled: size,
+                canvas: size,
+            });
+        }
+        (None, None) => return None,
+    };
+
+    let width_scale = width as f32 / original_width;
+    let height_scale = height as f32 / original_height;
+
+    let scale = match mode {
+        ResizeMode::Stretch => {
+            return Some(ResizePlan {
+                scaled: (width, height),
+                canvas: (width, height),
+            })
+        }
+        ResizeMode::Fit | ResizeMode::Pad => width_scale.min(height_scale),
+        ResizeMode::Fill => width_scale.max(height_scale),
+    };
+
+    let scaled = (
+        ((original_width * scale).round() as u32).max(1),
+        ((original_height * scale).round() as u32).max(1),
+    );
+
+    // Rounding can leave the content a pixel short of the box when filling,
+    // or a pixel past it when fitting
+    let scaled = match mode {
+        ResizeMode::Fill => (scaled.0.max(width), scaled.1.max(height)),
+        _ => (scaled.0.min(width), scaled.1.min(height)),
+    };
+
+    let canvas = match mode {
+        ResizeMode::Fit => scaled,
+        _ => (width, height),
+    };
+
+    return Some(ResizePlan { scaled, canvas });
+}
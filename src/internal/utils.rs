@@ -1,4 +1,4 @@
-use std::fs;
+use std::{fs, io, path};
 
 use log::debug;
 
@@ -33,3 +33,24 @@ pub fn normalize_command(command: &str) -> String {
         Err(_) => command.to_string(),
     };
 }
+
+/// Lists the files inside a directory, sorted by path
+pub fn list_files(directory: &path::Path, recursive: bool) -> io::Result<Vec<path::PathBuf>> {
+    let mut files = Vec::new();
+
+    for entry in fs::read_dir(directory)? {
+        let entry_path = entry?.path();
+
+        if entry_path.is_dir() {
+            if recursive {
+                files.extend(list_files(&entry_path, recursive)?);
+            }
+        } else {
+            files.push(entry_path);
+        }
+    }
+
+    files.sort();
+
+    return Ok(files);
+}
@@ -0,0 +1,116 @@
+use std::collections::BTreeMap;
+use std::{fs, path};
+
+use serde::Deserialize;
+use thiserror::Error;
+
+/// Recipe of operations described in a job file
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Job {
+    /// Input of the first step, when it does not set its own
+    pub input: Option<String>,
+    pub steps: Vec<JobStep>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JobStep {
+    /// Mode and command to run, such as "image resize"
+    pub command: String,
+    /// Input of the step. Defaults to the output of the previous step
+    pub input: Option<String>,
+    pub output: Option<String>,
+    #[serde(default)]
+    pub args: StepArgs,
+}
+
+/// Arguments of a step, either as a table of option names to values or as a
+/// list passed verbatim, which keeps their order
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum StepArgs {
+    Table(BTreeMap<String, serde_json::Value>),
+    List(Vec<String>),
+}
+
+impl Default for StepArgs {
+    fn default() -> Self {
+        return StepArgs::Table(BTreeMap::new());
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum JobFileError {
+    #[error("{0}")]
+    IoError(std::io::Error),
+    #[error("{0}")]
+    TomlError(toml::de::Error),
+    #[error("{0}")]
+    YamlError(serde_yaml::Error),
+    #[error("{0}")]
+    JsonError(serde_json::Error),
+    #[error("Unsupported job file format, expected toml, yaml or json")]
+    UnsupportedFormatError,
+    #[error("Unsupported value for argument {0}")]
+    UnsupportedValueError(String),
+}
+
+impl Job {
+    pub fn load(job_path: &path::Path) -> Result<Self, JobFileError> {
+        let contents = fs::read_to_string(job_path).map_err(|e| JobFileError::IoError(e))?;
+
+        let extension = job_path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase());
+
+        return match extension.as_deref() {
+            Some("toml") => toml::from_str(&contents).map_err(|e| JobFileError::TomlError(e)),
+            Some("yaml") | Some("yml") => {
+                serde_yaml::from_str(&contents).map_err(|e| JobFileError::YamlError(e))
+            }
+            Some("json") => serde_json::from_str(&contents).map_err(|e| JobFileError::JsonError(e)),
+            _ => Err(JobFileError::UnsupportedFormatError),
+        };
+    }
+}
+
+impl StepArgs {
+    /// Converts the arguments to their command line form. Booleans become
+    /// flags, lists repeat the option for every value and values are attached
+    /// with `=` so negative numbers are not mistaken for options.
+    pub fn to_command_line(&self) -> Result<Vec<String>, JobFileError> {
+        let table = match self {
+            StepArgs::List(list) => return Ok(list.clone()),
+            StepArgs::Table(table) => table,
+        };
+
+        let mut command_line = Vec::new();
+
+        for (name, value) in table.iter() {
+            let option = format!("--{}", name.replace('_', "-"));
+
+            let values = match value {
+                serde_json::Value::Array(values) => values.iter().collect(),
+                value => vec![value],
+            };
+
+            for value in values {
+                match value {
+                    serde_json::Value::Bool(true) => command_line.push(option.clone()),
+                    serde_json::Value::Bool(false) => {}
+                    serde_json::Value::String(value) => {
+                        command_line.push(format!("{}={}", option, value))
+                    }
+                    serde_json::Value::Number(value) => {
+                        command_line.push(format!("{}={}", option, value))
+                    }
+                    _ => return Err(JobFileError::UnsupportedValueError(name.clone())),
+                }
+            }
+        }
+
+        return Ok(command_line);
+    }
+}
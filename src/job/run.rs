@@ -0,0 +1,203 @@
+use std::path;
+
+use clap::{Args, Parser};
+use thiserror::Error;
+
+use super::file::{Job, JobFileError};
+use crate::cli::cli::{Cli, CliError};
+use crate::image::batch;
+use crate::internal::utils;
+
+/// Modes that can be used by the steps of a job
+const STEP_MODES: &[&str] = &["image", "video", "audio"];
+
+/// Long options of the step commands that take a file path, which are
+/// resolved against the directory of the job file like inputs and outputs
+const PATH_OPTIONS: &[&str] = &["--watermark", "--font", "--ir"];
+
+#[derive(Args)]
+pub struct RunCommand {
+    /// Only validate the job without running any step
+    #[clap(long)]
+    dry_run: bool,
+}
+
+#[derive(Debug, Error)]
+pub enum RunError {
+    #[error("{0}")]
+    JobFileError(JobFileError),
+    #[error("Job has no steps")]
+    NoStepsError,
+    #[error("Invalid job:\n{}", .0.join("\n"))]
+    ValidationError(Vec<String>),
+    #[error("Step {0} failed: {1}")]
+    StepError(usize, Box<CliError>),
+    #[error("No job file provided")]
+    NoJobError,
+}
+
+impl RunCommand {
+    pub fn execute(&self, input: Option<&str>) -> Result<(), RunError> {
+        let job_path = utils::to_absolute_path(input.ok_or(RunError::NoJobError)?);
+        let job = Job::load(&job_path).map_err(|e| RunError::JobFileError(e))?;
+
+        if job.steps.is_empty() {
+            return Err(RunError::NoStepsError);
+        }
+
+        let job_directory = job_path.parent().unwrap_or(path::Path::new(""));
+        let steps = validate(&job, job_directory)?;
+
+        if self.dry_run {
+            println!("Job is valid, {} step(s)", steps.len());
+            return Ok(());
+        }
+
+        for (index, (command, cli)) in steps.iter().enumerate() {
+            println!("Step {}/{}: {}", index + 1, steps.len(), command);
+
+            cli.execute()
+                .map_err(|e| RunError::StepError(index + 1, Box::new(e)))?;
+        }
+
+        return Ok(());
+    }
+}
+
+/// Parses every step with the same rules as the command line, collecting all
+/// the problems before anything runs. Relative paths are taken from the
+/// directory of the job file, so a job behaves the same wherever it is run.
+fn validate(job: &Job, job_directory: &path::Path) -> Result<Vec<(String, Cli)>, RunError> {
+    let mut steps = Vec::new();
+    let mut problems = Vec::new();
+    let mut previous_output = job
+        .input
+        .as_ref()
+        .map(|input| resolve(job_directory, input));
+    let mut outputs: Vec<String> = Vec::new();
+
+    for (index, step) in job.steps.iter().enumerate() {
+        let number = index + 1;
+        let command: Vec<&str> = step.command.split_whitespace().collect();
+
+        if !command
+            .first()
+            .is_some_and(|mode| STEP_MODES.contains(mode))
+        {
+            problems.push(format!(
+                "Step {}: command must start with one of {}",
+                number,
+                STEP_MODES.join(", ")
+            ));
+            continue;
+        }
+
+        let mut command_line = vec![env!("CARGO_PKG_NAME").to_string()];
+        command_line.extend(command.iter().map(|s| s.to_string()));
+
+        let input = step
+            .input
+            .as_ref()
+            .map(|input| resolve(job_directory, input))
+            .or(previous_output.clone());
+        let output = step
+            .output
+            .as_ref()
+            .map(|output| resolve(job_directory, output));
+
+        match &input {
+            Some(input) => {
+                if !outputs.contains(input) && !input_exists(input) {
+                    problems.push(format!("Step {}: input {} not found", number, input));
+                }
+
+                command_line.push(input.clone());
+            }
+            None => {
+                problems.push(format!("Step {}: no input and no previous output", number));
+                continue;
+            }
+        }
+
+        match step.args.to_command_line() {
+            Ok(args) => command_line.extend(resolve_path_options(job_directory, args)),
+            Err(e) => {
+                problems.push(format!("Step {}: {}", number, e));
+                continue;
+            }
+        }
+
+        if let Some(output) = &output {
+            command_line.push(format!("--output={}", output));
+        }
+
+        match Cli::try_parse_from(&command_line) {
+            Ok(cli) => steps.push((step.command.clone(), cli)),
+            Err(e) => {
+                let message = e.to_string();
+                let message = message.lines().next().unwrap_or_default();
+
+                problems.push(format!(
+                    "Step {}: {}",
+                    number,
+                    message.trim_start_matches("error: ")
+                ));
+            }
+        }
+
+        if let Some(output) = &output {
+            outputs.push(output.clone());
+        }
+
+        previous_output = output.or(previous_output);
+    }
+
+    if !problems.is_empty() {
+        return Err(RunError::ValidationError(problems));
+    }
+
+    return Ok(steps);
+}
+
+/// Whether an input that is not produced by an earlier step can be read. Glob
+/// patterns and stdin are left for the step itself to resolve.
+fn input_exists(input: &str) -> bool {
+    return input == "-" || batch::is_batch_input(input) || utils::to_absolute_path(input).exists();
+}
+
+/// Joins a relative path to the directory of the job file. Stdin and stdout
+/// are kept as they are.
+fn resolve(job_directory: &path::Path, file: &str) -> String {
+    if file == "-" {
+        return file.to_string();
+    }
+
+    return job_directory.join(file).to_string_lossy().to_string();
+}
+
+/// Resolves the values of the path options, given either as `--option=value`
+/// or as `--option value`
+fn resolve_path_options(job_directory: &path::Path, args: Vec<String>) -> Vec<String> {
+    let mut resolved = Vec::new();
+    let mut takes_path = false;
+
+    for arg in args {
+        if takes_path {
+            resolved.push(resolve(job_directory, &arg));
+            takes_path = false;
+            continue;
+        }
+
+        match arg.split_once('=') {
+            Some((option, value)) if PATH_OPTIONS.contains(&option) => {
+                resolved.push(format!("{}={}", option, resolve(job_directory, value)));
+            }
+            _ => {
+                takes_path = PATH_OPTIONS.contains(&arg.as_str());
+                resolved.push(arg);
+            }
+        }
+    }
+
+    return resolved;
+}
@@ -1,6 +1,7 @@
 pub mod audio;
 pub mod cli;
 pub mod image;
+pub mod job;
 pub mod video;
 
 mod internal;
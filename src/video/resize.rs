@@ -3,30 +3,43 @@ use log::info;
 use thiserror::Error;
 
 use super::ffmpeg::{ffmpeg, ffprobe};
+use super::utils as video_utils;
+use crate::internal::resize::{self, ResizeMode};
 use crate::internal::utils;
 
 #[derive(Args)]
 pub struct ResizeCommand {
-    /// New width of the video
-    #[clap(short, long)]
-    width: u32,
+    /// New width of the video. Derived from the height and the aspect ratio
+    /// when not set
+    #[clap(short, long, required_unless_present = "height")]
+    width: Option<u32>,
 
-    /// Whether the width is a percentage
+    /// Whether the width is a percentage. Values above 100 upscale
     #[clap(long)]
     width_as_percentage: bool,
 
-    /// New height of the video
+    /// New height of the video. Derived from the width and the aspect ratio
+    /// when not set
     #[clap(short('H'), long)]
-    height: u32,
+    height: Option<u32>,
 
-    /// Whether the height is a percentage
+    /// Whether the height is a percentage. Values above 100 upscale
     #[clap(long)]
     height_as_percentage: bool,
 
-    /// Whether to keep the aspect ratio
-    #[clap(short('r'), long)]
+    /// Whether to keep the aspect ratio. Same as --mode fit
+    #[clap(short('r'), long, conflicts_with = "mode")]
     keep_ratio: bool,
 
+    /// How to fit the video into the size when both dimensions are given.
+    /// Defaults to stretch
+    #[clap(short, long)]
+    mode: Option<ResizeMode>,
+
+    /// Color of the padding in pad mode. Format: (r, g, b, a)
+    #[clap(short('c'), long)]
+    fill_color: Option<String>,
+
     /// Output path
     #[clap(short, long)]
     output: String,
@@ -38,6 +51,10 @@ pub enum ResizeError {
     ProbeError(ffprobe::FfprobeError),
     #[error("{0}")]
     FfmpegError(ffmpeg::FfmpegError),
+    #[error("Either a width or a height is required")]
+    MissingSizeError,
+    #[error("Parse error")]
+    ParseError,
 }
 
 impl ResizeCommand {
@@ -48,40 +65,51 @@ impl ResizeCommand {
         let (video_width, video_height) =
             ffprobe::get_video_dimensions(&input_path).map_err(|e| ResizeError::ProbeError(e))?;
 
-        let ratio = video_width as f32 / video_height as f32;
-        let mut new_width = self.get_new_width(video_width);
-        let mut new_height = self.get_new_height(video_height);
+        let mode = match self.mode {
+            Some(mode) => mode,
+            None if self.keep_ratio => ResizeMode::Fit,
+            None => ResizeMode::Stretch,
+        };
 
-        let mut stream = ffmpeg::Ffmpeg::input(0, &input_path);
+        let plan = resize::get_resize_plan(
+            (video_width, video_height),
+            resize::get_dimension(self.width, self.width_as_percentage, video_width),
+            resize::get_dimension(self.height, self.height_as_percentage, video_height),
+            mode,
+        )
+        .ok_or(ResizeError::MissingSizeError)?;
 
-        if self.keep_ratio {
-            let mut possible_width = (new_height as f32 * ratio).round() as u32;
-            let mut possible_height = (new_width as f32 / ratio).round() as u32;
+        // Most encoders only accept even dimensions
+        let (scaled_width, scaled_height) = (to_even(plan.scaled.0), to_even(plan.scaled.1));
+        let (new_width, new_height) = (to_even(plan.canvas.0), to_even(plan.canvas.1));
 
-            let possible_width_difference = (new_width as i32 - possible_width as i32).abs();
-            let possible_height_difference = (new_height as i32 - possible_height as i32).abs();
+        info!(
+            "Resizing video to {}x{} (original: {}x{})",
+            new_width, new_height, video_width, video_height
+        );
 
-            if possible_width_difference < possible_height_difference {
-                if possible_width % 2 != 0 {
-                    possible_width += 1;
-                }
+        let mut stream = ffmpeg::Ffmpeg::input(0, &input_path);
 
-                new_width = possible_width;
-            } else {
-                if possible_height % 2 != 0 {
-                    possible_height += 1;
-                }
+        stream.scale(scaled_width, scaled_height);
 
-                new_height = possible_height;
+        match mode {
+            ResizeMode::Fill => {
+                stream.crop(new_width, new_height);
+            }
+            ResizeMode::Pad => {
+                let fill_color = match self.fill_color {
+                    Some(ref color) => {
+                        video_utils::from_str_to_hex(color).map_err(|_e| ResizeError::ParseError)?
+                    }
+                    None => String::from("#000000"),
+                };
+
+                stream.pad(new_width, new_height, &fill_color);
             }
+            ResizeMode::Stretch | ResizeMode::Fit => {}
         }
 
-        info!(
-            "Resizing video to {}x{} (original: {}x{})",
-            new_width, new_height, video_width, video_height
-        );
-
-        stream.scale(new_width, new_height).output(&output_path);
+        stream.output(&output_path);
 
         stream.execute().map_err(|e| ResizeError::FfmpegError(e))?;
 
@@ -89,20 +117,8 @@ impl ResizeCommand {
 
         return Ok(());
     }
+}
 
-    pub fn get_new_height(&self, video_height: u32) -> u32 {
-        if self.height_as_percentage {
-            return (video_height as f32 * self.height.clamp(0, 100) as f32 / 100.0).round() as u32;
-        }
-
-        return self.height;
-    }
-
-    pub fn get_new_width(&self, video_width: u32) -> u32 {
-        if self.width_as_percentage {
-            return (video_width as f32 * self.width.clamp(0, 100) as f32 / 100.0).round() as u32;
-        }
-
-        return self.width;
-    }
+fn to_even(value: u32) -> u32 {
+    return value + value % 2;
 }
@@ -1,8 +1,11 @@
-use clap::{Args, ValueEnum};
-use log::error;
+use std::{env, fs, process};
+
+use clap::Args;
+use log::{error, warn};
 use thiserror::Error;
 
 use super::ffmpeg::ffprobe;
+use crate::image::watermark::PlacementOptions;
 use crate::internal::utils;
 use crate::video::ffmpeg::ffmpeg;
 
@@ -12,10 +15,6 @@ pub struct VideoWatermarkCommand {
     #[clap(short, long)]
     watermark: String,
 
-    /// Position to place the overlay
-    #[clap(short, long, default_value = "center")]
-    position: VideoWatermarkPosition,
-
     /// Opacity of the overlay. Note: value will be clamped between 0.0 and 1.0
     #[clap(short('O'), long, default_value = "1.0")]
     opacity: f32,
@@ -25,20 +24,14 @@ pub struct VideoWatermarkCommand {
     #[clap(short, long)]
     scale: Option<f32>,
 
+    #[clap(flatten)]
+    placement: PlacementOptions,
+
     /// Path to output file
     #[clap(short, long)]
     output: String,
 }
 
-#[derive(ValueEnum, Clone)]
-pub enum VideoWatermarkPosition {
-    TopLeft,
-    TopRight,
-    Center,
-    BottomLeft,
-    BottomRight,
-}
-
 #[derive(Debug, Error)]
 pub enum VideoWatermarkError {
     #[error("Image error")]
@@ -67,45 +60,77 @@ impl VideoWatermarkCommand {
         let mut watermark_height = watermark.height();
         let watermark_ratio = watermark_width as f32 / watermark_height as f32;
 
-        let mut video_stream = ffmpeg::Ffmpeg::input(0, &input_path);
-        let mut watermark_stream = ffmpeg::Ffmpeg::input(1, &watermark_path);
-        watermark_stream.opacity(self.opacity.clamp(0f32, 1f32));
-
         if let Some(scale) = self.scale {
             watermark_width = (video_width as f32 * scale.clamp(0f32, 1f32)) as u32;
             watermark_height = (watermark_width as f32 / watermark_ratio) as u32;
-            watermark_stream.scale(watermark_width, watermark_height);
         }
 
-        watermark_width = watermark_width.clamp(0, video_width);
-        watermark_height = watermark_height.clamp(0, video_height);
+        let (fitted_width, fitted_height) = match self.placement.fit(
+            (video_width, video_height),
+            (watermark_width, watermark_height),
+        ) {
+            Some(size) => size,
+            None => {
+                error!("No space left for the watermark inside the margins");
+                return Err(VideoWatermarkError::DimensionError);
+            }
+        };
 
-        let mut x = 0;
-        let mut y = 0;
+        if (fitted_width, fitted_height) != (watermark_width, watermark_height) {
+            warn!(
+                "Watermark is bigger than the video, scaling it down to {}x{}",
+                fitted_width, fitted_height
+            );
+        }
 
-        match self.position {
-            VideoWatermarkPosition::TopLeft => {}
-            VideoWatermarkPosition::TopRight => {
-                x = video_width - watermark_width;
-            }
-            VideoWatermarkPosition::Center => {
-                x = (video_width - watermark_width) / 2;
-                y = (video_height - watermark_height) / 2;
-            }
-            VideoWatermarkPosition::BottomLeft => {
-                y = video_height - watermark_height;
-            }
-            VideoWatermarkPosition::BottomRight => {
-                x = video_width - watermark_width;
-                y = video_height - watermark_height;
-            }
+        let mut video_stream = ffmpeg::Ffmpeg::input(0, &input_path);
+
+        // FFmpeg cannot repeat an overlay, so the pattern is drawn on a
+        // transparent picture the size of the video instead
+        let pattern_path =
+            env::temp_dir().join(format!("media_tools_watermark_{}.png", process::id()));
+
+        let (overlay_path, x, y) = if self.placement.tile {
+            let watermark = watermark.resize_exact(
+                fitted_width,
+                fitted_height,
+                image::imageops::FilterType::Lanczos3,
+            );
+
+            self.placement
+                .tile(&watermark, (video_width, video_height))
+                .save(&pattern_path)
+                .map_err(|e| VideoWatermarkError::ImageError(e))?;
+
+            (pattern_path.clone(), 0, 0)
+        } else {
+            let (x, y) = self
+                .placement
+                .get_offset((video_width, video_height), (fitted_width, fitted_height));
+
+            (watermark_path, x, y)
+        };
+
+        let mut watermark_stream = ffmpeg::Ffmpeg::input(1, &overlay_path);
+        watermark_stream.opacity(self.opacity.clamp(0f32, 1f32));
+
+        if !self.placement.tile
+            && (fitted_width, fitted_height) != (watermark.width(), watermark.height())
+        {
+            watermark_stream.scale(fitted_width, fitted_height);
         }
 
         video_stream
             .overlay(&watermark_stream, x, y)
             .output(&output_path);
 
-        return match video_stream.execute() {
+        let result = video_stream.execute();
+
+        if self.placement.tile {
+            let _ = fs::remove_file(&pattern_path);
+        }
+
+        return match result {
             Ok(_) => {
                 println!("Video saved to {}", output_path.display());
                 Ok(())